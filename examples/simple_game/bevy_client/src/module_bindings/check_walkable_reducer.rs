@@ -7,8 +7,8 @@ use spacetimedb_sdk::__codegen::{self as __sdk, __lib, __sats, __ws};
 #[derive(__lib::ser::Serialize, __lib::de::Deserialize, Clone, PartialEq, Debug)]
 #[sats(crate = __lib)]
 pub(super) struct CheckWalkableArgs {
-    pub x: u32,
-    pub y: u32,
+    pub x: i32,
+    pub y: i32,
 }
 
 impl From<CheckWalkableArgs> for super::Reducer {
@@ -36,7 +36,7 @@ pub trait check_walkable {
     /// This method returns immediately, and errors only if we are unable to send the request.
     /// The reducer will run asynchronously in the future,
     ///  and its status can be observed by listening for [`Self::on_check_walkable`] callbacks.
-    fn check_walkable(&self, x: u32, y: u32) -> __sdk::Result<()>;
+    fn check_walkable(&self, x: i32, y: i32) -> __sdk::Result<()>;
     /// Register a callback to run whenever we are notified of an invocation of the reducer `check_walkable`.
     ///
     /// Callbacks should inspect the [`__sdk::ReducerEvent`] contained in the [`super::ReducerEventContext`]
@@ -46,7 +46,7 @@ pub trait check_walkable {
     /// to cancel the callback.
     fn on_check_walkable(
         &self,
-        callback: impl FnMut(&super::ReducerEventContext, &u32, &u32) + Send + 'static,
+        callback: impl FnMut(&super::ReducerEventContext, &i32, &i32) + Send + 'static,
     ) -> CheckWalkableCallbackId;
     /// Cancel a callback previously registered by [`Self::on_check_walkable`],
     /// causing it not to run in the future.
@@ -54,13 +54,13 @@ pub trait check_walkable {
 }
 
 impl check_walkable for super::RemoteReducers {
-    fn check_walkable(&self, x: u32, y: u32) -> __sdk::Result<()> {
+    fn check_walkable(&self, x: i32, y: i32) -> __sdk::Result<()> {
         self.imp
             .call_reducer("check_walkable", CheckWalkableArgs { x, y })
     }
     fn on_check_walkable(
         &self,
-        mut callback: impl FnMut(&super::ReducerEventContext, &u32, &u32) + Send + 'static,
+        mut callback: impl FnMut(&super::ReducerEventContext, &i32, &i32) + Send + 'static,
     ) -> CheckWalkableCallbackId {
         CheckWalkableCallbackId(self.imp.on_reducer(
             "check_walkable",
@@ -20,6 +20,7 @@ pub mod tiled_map_table;
 pub mod tiled_map_type;
 pub mod tiled_object_table;
 pub mod tiled_object_type;
+pub mod tiled_point_type;
 pub mod tiled_property_table;
 pub mod tiled_property_type;
 pub mod tiled_tile_table;
@@ -49,6 +50,7 @@ pub use tiled_map_table::*;
 pub use tiled_map_type::TiledMap;
 pub use tiled_object_table::*;
 pub use tiled_object_type::TiledObject;
+pub use tiled_point_type::TiledPoint;
 pub use tiled_property_table::*;
 pub use tiled_property_type::TiledProperty;
 pub use tiled_tile_table::*;
@@ -8,8 +8,8 @@ use spacetimedb_sdk::__codegen::{self as __sdk, __lib, __sats, __ws};
 #[sats(crate = __lib)]
 pub(super) struct QueryTileArgs {
     pub layer_id: u32,
-    pub x: u32,
-    pub y: u32,
+    pub x: i32,
+    pub y: i32,
 }
 
 impl From<QueryTileArgs> for super::Reducer {
@@ -38,7 +38,7 @@ pub trait query_tile {
     /// This method returns immediately, and errors only if we are unable to send the request.
     /// The reducer will run asynchronously in the future,
     ///  and its status can be observed by listening for [`Self::on_query_tile`] callbacks.
-    fn query_tile(&self, layer_id: u32, x: u32, y: u32) -> __sdk::Result<()>;
+    fn query_tile(&self, layer_id: u32, x: i32, y: i32) -> __sdk::Result<()>;
     /// Register a callback to run whenever we are notified of an invocation of the reducer `query_tile`.
     ///
     /// Callbacks should inspect the [`__sdk::ReducerEvent`] contained in the [`super::ReducerEventContext`]
@@ -48,7 +48,7 @@ pub trait query_tile {
     /// to cancel the callback.
     fn on_query_tile(
         &self,
-        callback: impl FnMut(&super::ReducerEventContext, &u32, &u32, &u32) + Send + 'static,
+        callback: impl FnMut(&super::ReducerEventContext, &u32, &i32, &i32) + Send + 'static,
     ) -> QueryTileCallbackId;
     /// Cancel a callback previously registered by [`Self::on_query_tile`],
     /// causing it not to run in the future.
@@ -56,13 +56,13 @@ pub trait query_tile {
 }
 
 impl query_tile for super::RemoteReducers {
-    fn query_tile(&self, layer_id: u32, x: u32, y: u32) -> __sdk::Result<()> {
+    fn query_tile(&self, layer_id: u32, x: i32, y: i32) -> __sdk::Result<()> {
         self.imp
             .call_reducer("query_tile", QueryTileArgs { layer_id, x, y })
     }
     fn on_query_tile(
         &self,
-        mut callback: impl FnMut(&super::ReducerEventContext, &u32, &u32, &u32) + Send + 'static,
+        mut callback: impl FnMut(&super::ReducerEventContext, &u32, &i32, &i32) + Send + 'static,
     ) -> QueryTileCallbackId {
         QueryTileCallbackId(self.imp.on_reducer(
             "query_tile",
@@ -16,6 +16,15 @@ pub struct TiledLayer {
     pub offset_x: i32,
     pub offset_y: i32,
     pub z_order: u32,
+    pub parent_layer_id: Option<u32>,
+    pub tint_color: Option<String>,
+    pub parallax_x: f32,
+    pub parallax_y: f32,
+    pub class: String,
+    pub min_x: Option<i32>,
+    pub min_y: Option<i32>,
+    pub max_x: Option<i32>,
+    pub max_y: Option<i32>,
 }
 
 impl __sdk::InModule for TiledLayer {
@@ -15,6 +15,18 @@ pub struct TiledMap {
     pub tile_height: u32,
     pub orientation: String,
     pub background_color: Option<String>,
+    pub infinite: bool,
+    pub render_order: String,
+    pub stagger_axis: Option<String>,
+    pub stagger_index: Option<String>,
+    pub hex_side_length: Option<u32>,
+    pub parallax_origin_x: f32,
+    pub parallax_origin_y: f32,
+    pub class: String,
+    pub version: String,
+    pub tiled_version: String,
+    pub next_layer_id: u32,
+    pub next_object_id: u32,
 }
 
 impl __sdk::InModule for TiledMap {
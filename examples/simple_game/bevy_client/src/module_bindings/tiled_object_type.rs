@@ -4,6 +4,8 @@
 #![allow(unused, clippy::all)]
 use spacetimedb_sdk::__codegen::{self as __sdk, __lib, __sats, __ws};
 
+use super::tiled_point_type::TiledPoint;
+
 #[derive(__lib::ser::Serialize, __lib::de::Deserialize, Clone, PartialEq, Debug)]
 #[sats(crate = __lib)]
 pub struct TiledObject {
@@ -18,6 +20,11 @@ pub struct TiledObject {
     pub rotation: f32,
     pub visible: bool,
     pub shape: String,
+    pub points: Vec<TiledPoint>,
+    pub gid: Option<u32>,
+    pub flip_h: bool,
+    pub flip_v: bool,
+    pub flip_d: bool,
 }
 
 impl __sdk::InModule for TiledObject {
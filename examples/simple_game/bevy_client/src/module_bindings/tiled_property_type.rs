@@ -13,6 +13,7 @@ pub struct TiledProperty {
     pub key: String,
     pub value: String,
     pub value_type: String,
+    pub property_type: String,
 }
 
 impl __sdk::InModule for TiledProperty {
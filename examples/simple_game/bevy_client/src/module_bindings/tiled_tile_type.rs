@@ -9,8 +9,8 @@ use spacetimedb_sdk::__codegen::{self as __sdk, __lib, __sats, __ws};
 pub struct TiledTile {
     pub tile_id: u64,
     pub layer_id: u32,
-    pub x: u32,
-    pub y: u32,
+    pub x: i32,
+    pub y: i32,
     pub gid: u32,
     pub flip_h: bool,
     pub flip_v: bool,
@@ -10,14 +10,32 @@ pub struct TiledTileset {
     pub tileset_id: u32,
     pub map_id: u32,
     pub tileset_index: u32,
+    pub first_gid: u32,
     pub name: String,
     pub tile_width: u32,
     pub tile_height: u32,
     pub tile_count: u32,
     pub columns: u32,
+    pub spacing: u32,
+    pub margin: u32,
+    pub tile_offset_x: i32,
+    pub tile_offset_y: i32,
+    pub object_alignment: String,
+    pub tile_render_size: String,
+    pub fill_mode: String,
+    pub grid_orientation: String,
+    pub grid_width: u32,
+    pub grid_height: u32,
+    pub allow_flip_h: bool,
+    pub allow_flip_v: bool,
+    pub allow_rotate: bool,
+    pub prefer_untransformed: bool,
     pub image_source: Option<String>,
     pub image_width: Option<u32>,
     pub image_height: Option<u32>,
+    pub source: Option<String>,
+    pub source_hash: Option<u64>,
+    pub definition_tileset_id: u32,
 }
 
 impl __sdk::InModule for TiledTileset {
@@ -239,8 +239,8 @@ fn handle_tile(conn: &DbConnection, args: &[&str]) {
     }
 
     let layer_id = args[0].parse::<u32>();
-    let x = args[1].parse::<u32>();
-    let y = args[2].parse::<u32>();
+    let x = args[1].parse::<i32>();
+    let y = args[2].parse::<i32>();
 
     match (layer_id, x, y) {
         (Ok(lid), Ok(tx), Ok(ty)) => {
@@ -488,8 +488,8 @@ fn handle_walkable(conn: &DbConnection, args: &[&str]) {
         return;
     }
 
-    let x = args[0].parse::<u32>();
-    let y = args[1].parse::<u32>();
+    let x = args[0].parse::<i32>();
+    let y = args[1].parse::<i32>();
 
     match (x, y) {
         (Ok(tx), Ok(ty)) => {
@@ -67,6 +67,7 @@ pub fn get_map_info(ctx: &ReducerContext, map_id: u32) -> Result<(), String> {
                 m.tile_height
             );
             log::info!("Orientation: {}", m.orientation);
+            log::info!("Render order: {}", m.render_order);
 
             // Count layers
             let layer_count = ctx
@@ -98,7 +99,7 @@ pub fn get_map_info(ctx: &ReducerContext, map_id: u32) -> Result<(), String> {
 ///
 /// Returns information about the tile at the given coordinates in a layer.
 #[reducer]
-pub fn query_tile(ctx: &ReducerContext, layer_id: u32, x: u32, y: u32) -> Result<(), String> {
+pub fn query_tile(ctx: &ReducerContext, layer_id: u32, x: i32, y: i32) -> Result<(), String> {
     log::info!("Querying tile at ({}, {}) in layer {}", x, y, layer_id);
 
     let tile = ctx
@@ -118,14 +119,25 @@ pub fn query_tile(ctx: &ReducerContext, layer_id: u32, x: u32, y: u32) -> Result
             );
 
             // Find which tileset this tile belongs to
-            let tilesets: Vec<_> = ctx.db.tiled_tileset().iter().collect();
-            for tileset in tilesets {
-                // Since we store tileset_index, we can identify tilesets by their position
-                log::info!(
-                    "Tileset: '{}' at index {}",
-                    tileset.name,
-                    tileset.tileset_index
-                );
+            let map_id = ctx
+                .db
+                .tiled_layer()
+                .layer_id()
+                .find(layer_id)
+                .map(|layer| layer.map_id)
+                .ok_or("Layer not found")?;
+            if let Some((tileset_id, local_id)) = resolve_gid(ctx, map_id, t.gid) {
+                if let Some(tileset) = ctx.db.tiled_tileset().tileset_id().find(tileset_id) {
+                    log::info!("Tile {} of tileset '{}'", local_id, tileset.name);
+                }
+
+                // Per-tile metadata, e.g. collision=true on the demo map's wall tile
+                if let Some(tileset_tile) = find_tileset_tile(ctx, tileset_id, local_id) {
+                    log::info!("Tile class: '{}'", tileset_tile.tile_type);
+                    for prop in get_properties(ctx, "tileset_tile", tileset_tile.tileset_tile_id) {
+                        log::info!("  {} = {}", prop.key, prop.value);
+                    }
+                }
             }
 
             Ok(())
@@ -184,7 +196,7 @@ pub fn find_spawns(ctx: &ReducerContext) -> Result<(), String> {
 /// This is a practical example of using map data for game logic.
 /// Note: Reducers can only return Result<(), E>, not Result<T, E>
 #[reducer]
-pub fn check_walkable(ctx: &ReducerContext, x: u32, y: u32) -> Result<(), String> {
+pub fn check_walkable(ctx: &ReducerContext, x: i32, y: i32) -> Result<(), String> {
     log::info!("Checking if ({}, {}) is walkable", x, y);
 
     // Find the collision layer (usually layer 1 in the demo map)
@@ -240,6 +252,9 @@ pub fn list_layers(ctx: &ReducerContext, map_id: u32) -> Result<(), String> {
             layer.visible,
             layer.opacity
         );
+        if let Some(parent_layer_id) = layer.parent_layer_id {
+            log::info!("      Inside group layer {}", parent_layer_id);
+        }
 
         // Count tiles/objects in this layer
         match layer.layer_type.as_str() {
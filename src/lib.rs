@@ -12,7 +12,7 @@
 //! #[reducer]
 //! pub fn load_map(ctx: &ReducerContext) -> Result<(), String> {
 //!     // Embed the TMX file at compile time
-//!     const MAP_DATA: &str = include_str!("../assets/map.tmx");
+//!     const MAP_DATA: &str = include_str!("../examples/simple_game/assets/demo_map.tmx");
 //!
 //!     // Parse and store in database
 //!     load_tmx_map_from_str(ctx, "level1", MAP_DATA)?;
@@ -24,9 +24,11 @@
 //!
 //! SpacetimeDB modules can't access the filesystem. Use `load_tmx_map_from_str()` with
 //! `include_str!()` to embed maps at compile time, or have clients send TMX content as
-//! reducer parameters.
+//! reducer parameters. Maps with external tilesets go through `load_tmx_map_with_sources()`,
+//! which takes the `.tsx` content the same way.
 
-use spacetimedb::{table, ReducerContext, Table};
+use spacetimedb::{table, ReducerContext, SpacetimeType, Table};
+use std::collections::HashMap;
 
 // ============================================================================
 // Table Definitions
@@ -58,8 +60,44 @@ pub struct TiledMap {
     /// Map orientation (orthogonal, isometric, staggered, hexagonal)
     pub orientation: String,
 
-    /// Background color in hex format (e.g., "#ff0000")
+    /// Background color in `#rrggbbaa` format (e.g., "#ff0000ff")
     pub background_color: Option<String>,
+
+    /// Whether the map is infinite (tile data stored in chunks)
+    pub infinite: bool,
+
+    /// Order tiles are drawn in (right-down, right-up, left-down, left-up)
+    pub render_order: String,
+
+    /// Staggered axis of staggered and hexagonal maps ("x" or "y")
+    pub stagger_axis: Option<String>,
+
+    /// Whether odd or even rows/columns are shifted on staggered and hexagonal maps
+    pub stagger_index: Option<String>,
+
+    /// Length of the side of a hexagonal tile in pixels (hexagonal maps only)
+    pub hex_side_length: Option<u32>,
+
+    /// Horizontal origin of parallax scrolling, in pixels
+    pub parallax_origin_x: f32,
+
+    /// Vertical origin of parallax scrolling, in pixels
+    pub parallax_origin_y: f32,
+
+    /// Class of the map (empty if not set)
+    pub class: String,
+
+    /// TMX format version
+    pub version: String,
+
+    /// Version of Tiled that saved the map (empty if not recorded)
+    pub tiled_version: String,
+
+    /// Next layer ID Tiled will assign (0 if not recorded)
+    pub next_layer_id: u32,
+
+    /// Next object ID Tiled will assign (0 if not recorded)
+    pub next_object_id: u32,
 }
 
 /// Represents a layer in a Tiled map
@@ -93,7 +131,65 @@ pub struct TiledLayer {
     pub offset_y: i32,
 
     /// Layer ordering (lower numbers render first)
+    ///
+    /// Layers are numbered from 0 in each map, in the order they appear in the file: a
+    /// group layer comes right before the layers inside it, which come before the group's
+    /// next sibling. All loaders number layers this way.
     pub z_order: u32,
+
+    /// Group layer containing this layer (None for top-level layers)
+    pub parent_layer_id: Option<u32>,
+
+    /// Color multiplied with the layer's tiles and images, as `#rrggbbaa`
+    pub tint_color: Option<String>,
+
+    /// Horizontal parallax scrolling factor (1.0 scrolls with the map)
+    pub parallax_x: f32,
+
+    /// Vertical parallax scrolling factor (1.0 scrolls with the map)
+    pub parallax_y: f32,
+
+    /// Class of the layer (empty if not set)
+    pub class: String,
+
+    /// Smallest X coordinate of any tile in the layer (None if the layer has no tiles)
+    pub min_x: Option<i32>,
+
+    /// Smallest Y coordinate of any tile in the layer (None if the layer has no tiles)
+    pub min_y: Option<i32>,
+
+    /// Largest X coordinate of any tile in the layer (None if the layer has no tiles)
+    pub max_x: Option<i32>,
+
+    /// Largest Y coordinate of any tile in the layer (None if the layer has no tiles)
+    pub max_y: Option<i32>,
+}
+
+/// Represents the image of an image layer
+#[table(name = tiled_image_layer, public)]
+#[derive(Clone, Debug)]
+pub struct TiledImageLayer {
+    /// Reference to the image layer (one row per layer)
+    #[primary_key]
+    pub layer_id: u32,
+
+    /// Path to the image (None if no image is set)
+    pub image_source: Option<String>,
+
+    /// Image width in pixels
+    pub image_width: Option<u32>,
+
+    /// Image height in pixels
+    pub image_height: Option<u32>,
+
+    /// Color drawn as transparent, as `#rrggbbaa`
+    pub transparent_color: Option<String>,
+
+    /// Whether the image repeats horizontally
+    pub repeat_x: bool,
+
+    /// Whether the image repeats vertically
+    pub repeat_y: bool,
 }
 
 /// Represents a single tile in a tile layer
@@ -108,11 +204,11 @@ pub struct TiledTile {
     #[index(btree)]
     pub layer_id: u32,
 
-    /// X coordinate in the layer (in tiles)
-    pub x: u32,
+    /// X coordinate in the layer (in tiles, negative for chunks left of the origin)
+    pub x: i32,
 
-    /// Y coordinate in the layer (in tiles)
-    pub y: u32,
+    /// Y coordinate in the layer (in tiles, negative for chunks above the origin)
+    pub y: i32,
 
     /// Global tile ID (0 = empty tile)
     pub gid: u32,
@@ -142,6 +238,9 @@ pub struct TiledTileset {
     /// Tileset index in the map (used to reference this tileset)
     pub tileset_index: u32,
 
+    /// First global tile ID of this tileset in the map (see [`resolve_gid`])
+    pub first_gid: u32,
+
     /// Name of the tileset
     pub name: String,
 
@@ -157,6 +256,49 @@ pub struct TiledTileset {
     /// Number of columns in the tileset
     pub columns: u32,
 
+    /// Spacing between tiles in the image, in pixels
+    pub spacing: u32,
+
+    /// Margin around the tiles in the image, in pixels
+    pub margin: u32,
+
+    /// Horizontal offset applied when drawing tiles, in pixels
+    pub tile_offset_x: i32,
+
+    /// Vertical offset applied when drawing tiles, in pixels
+    pub tile_offset_y: i32,
+
+    /// Alignment of tile objects (unspecified, topleft, top, topright, left, center,
+    /// right, bottomleft, bottom, bottomright)
+    pub object_alignment: String,
+
+    /// Size tiles are drawn at ("tile" for the tileset's tile size, "grid" for the map's)
+    pub tile_render_size: String,
+
+    /// How tiles are scaled when drawn at a different size ("stretch" or "preserve-aspect-fit")
+    pub fill_mode: String,
+
+    /// Orientation of the grid used for tile overlays (orthogonal or isometric)
+    pub grid_orientation: String,
+
+    /// Width of a grid cell in pixels
+    pub grid_width: u32,
+
+    /// Height of a grid cell in pixels
+    pub grid_height: u32,
+
+    /// Whether tiles may be flipped horizontally when painting (e.g. by Wang tools)
+    pub allow_flip_h: bool,
+
+    /// Whether tiles may be flipped vertically when painting
+    pub allow_flip_v: bool,
+
+    /// Whether tiles may be rotated when painting
+    pub allow_rotate: bool,
+
+    /// Whether untransformed tiles are preferred over transformed ones
+    pub prefer_untransformed: bool,
+
     /// Image source path (if applicable)
     pub image_source: Option<String>,
 
@@ -165,6 +307,224 @@ pub struct TiledTileset {
 
     /// Image height in pixels
     pub image_height: Option<u32>,
+
+    /// Normalized path of the external `.tsx` file (None if embedded)
+    ///
+    /// Relative to the map for the string loaders, or the path the file was read from
+    /// for `load_tmx_map`.
+    pub source: Option<String>,
+
+    /// Hash of the external file's content (None if embedded or stored without its content)
+    ///
+    /// Maps share an external tileset only when both its path and this hash match.
+    pub source_hash: Option<u64>,
+
+    /// Tileset whose rows hold the tile metadata (tileset tiles, animations, collision
+    /// shapes, Wang sets and tileset properties)
+    ///
+    /// Equal to `tileset_id`, except when another map already loaded the same version of
+    /// the same external tileset; the metadata is then shared with that map's tileset
+    /// instead of copied.
+    pub definition_tileset_id: u32,
+}
+
+/// Represents per-tile metadata from a tileset's `<tile>` entries
+///
+/// Only tiles with a class, a probability other than 1, an image, properties, collision
+/// shapes or an animation get a row; empty `<tile>` entries are skipped by every loader.
+#[table(name = tiled_tileset_tile, public)]
+#[derive(Clone, Debug)]
+pub struct TiledTilesetTile {
+    /// Unique identifier: the tileset ID in the high 32 bits and the local tile ID in the low 32 bits
+    #[primary_key]
+    pub tileset_tile_id: u64,
+
+    /// Reference to the parent tileset
+    #[index(btree)]
+    pub tileset_id: u32,
+
+    /// Tile ID local to the tileset
+    pub local_id: u32,
+
+    /// Type/class of the tile
+    pub tile_type: String,
+
+    /// Probability used by Tiled's terrain and random brushes
+    pub probability: f32,
+
+    /// Image source path (image collection tilesets only)
+    pub image_source: Option<String>,
+
+    /// Image width in pixels
+    pub image_width: Option<u32>,
+
+    /// Image height in pixels
+    pub image_height: Option<u32>,
+
+    /// Left edge of the part of the image the tile uses (Tiled 1.9+, defaults to 0)
+    pub image_rect_x: Option<u32>,
+
+    /// Top edge of the part of the image the tile uses (Tiled 1.9+, defaults to 0)
+    pub image_rect_y: Option<u32>,
+
+    /// Width of the part of the image the tile uses (defaults to the image width)
+    pub image_rect_width: Option<u32>,
+
+    /// Height of the part of the image the tile uses (defaults to the image height)
+    pub image_rect_height: Option<u32>,
+}
+
+/// Represents one frame of an animated tileset tile
+#[table(name = tiled_tile_animation, public)]
+#[derive(Clone, Debug)]
+pub struct TiledTileAnimation {
+    /// Unique identifier for the frame
+    #[primary_key]
+    pub frame_id: u64,
+
+    /// Reference to the animated `TiledTilesetTile`
+    #[index(btree)]
+    pub tileset_tile_id: u64,
+
+    /// Reference to the tileset
+    pub tileset_id: u32,
+
+    /// Local ID of the animated tile
+    pub local_id: u32,
+
+    /// Position of this frame in the animation (starting at 0)
+    pub frame_index: u32,
+
+    /// Local ID of the tile shown during this frame
+    pub frame_tile_id: u32,
+
+    /// How long the frame is shown, in milliseconds
+    pub duration_ms: u32,
+}
+
+/// A vertex of a polygon or polyline, in pixels relative to its object's position
+#[derive(SpacetimeType, Clone, Copy, Debug, PartialEq)]
+pub struct TiledPoint {
+    /// X offset in pixels
+    pub x: f32,
+
+    /// Y offset in pixels
+    pub y: f32,
+}
+
+/// Represents a collision shape drawn on a tileset tile in Tiled's collision editor
+///
+/// Coordinates are in pixels relative to the top-left corner of the tile.
+#[table(name = tiled_tile_collision, public)]
+#[derive(Clone, Debug)]
+pub struct TiledTileCollision {
+    /// Unique identifier for the collision shape
+    #[primary_key]
+    pub collision_id: u64,
+
+    /// Reference to the `TiledTilesetTile` this shape belongs to
+    #[index(btree)]
+    pub tileset_tile_id: u64,
+
+    /// Name of the shape
+    pub name: String,
+
+    /// Type/class of the shape
+    pub obj_type: String,
+
+    /// X position in pixels
+    pub x: f32,
+
+    /// Y position in pixels
+    pub y: f32,
+
+    /// Width in pixels (0 for point shapes)
+    pub width: f32,
+
+    /// Height in pixels (0 for point shapes)
+    pub height: f32,
+
+    /// Rotation in degrees (clockwise)
+    pub rotation: f32,
+
+    /// Whether the shape is visible
+    pub visible: bool,
+
+    /// Shape type (rectangle, ellipse, point, polygon, polyline)
+    pub shape: String,
+
+    /// Vertices of polygon and polyline shapes (empty for other shapes)
+    pub points: Vec<TiledPoint>,
+}
+
+/// Represents a Wang set (terrain set) defined in a tileset
+#[table(name = tiled_wang_set, public)]
+#[derive(Clone, Debug)]
+pub struct TiledWangSet {
+    /// Unique identifier for the Wang set
+    #[primary_key]
+    pub wang_set_id: u32,
+
+    /// Reference to the parent tileset
+    #[index(btree)]
+    pub tileset_id: u32,
+
+    /// Name of the Wang set
+    pub name: String,
+
+    /// Wang set type (corner, edge, mixed)
+    pub wang_type: String,
+
+    /// Local ID of the tile representing the set, if any
+    pub tile_id: Option<u32>,
+}
+
+/// Represents a color (terrain) of a Wang set
+#[table(name = tiled_wang_color, public)]
+#[derive(Clone, Debug)]
+pub struct TiledWangColor {
+    /// Unique identifier for the Wang color
+    #[primary_key]
+    pub wang_color_id: u32,
+
+    /// Reference to the parent Wang set
+    #[index(btree)]
+    pub wang_set_id: u32,
+
+    /// Index of the color in its set, as used by `TiledWangTile::wang_id` (starting at 1)
+    pub color_index: u32,
+
+    /// Name of the color, e.g. "grass"
+    pub name: String,
+
+    /// Display color in `#rrggbbaa` form
+    pub color: String,
+
+    /// Local ID of the tile representing the color, if any
+    pub tile_id: Option<u32>,
+
+    /// Probability used when Tiled picks tiles of this color
+    pub probability: f32,
+}
+
+/// Represents the Wang colors assigned to one tile of a Wang set
+#[table(name = tiled_wang_tile, public)]
+#[derive(Clone, Debug)]
+pub struct TiledWangTile {
+    /// Unique identifier for the Wang tile
+    #[primary_key]
+    pub wang_tile_id: u64,
+
+    /// Reference to the parent Wang set
+    #[index(btree)]
+    pub wang_set_id: u32,
+
+    /// Local ID of the tile in the tileset
+    pub local_id: u32,
+
+    /// Color index of each edge and corner, clockwise from the top edge:
+    /// top, top-right, right, bottom-right, bottom, bottom-left, left, top-left (0 = none)
+    pub wang_id: Vec<u8>,
 }
 
 /// Represents an object in an object layer
@@ -205,6 +565,66 @@ pub struct TiledObject {
 
     /// Shape type (rectangle, ellipse, point, polygon, polyline, text)
     pub shape: String,
+
+    /// Vertices of polygon and polyline objects (empty for other shapes)
+    pub points: Vec<TiledPoint>,
+
+    /// Global tile ID for tile objects (flip flags removed, None for other objects)
+    pub gid: Option<u32>,
+
+    /// Whether the tile of a tile object is flipped horizontally
+    pub flip_h: bool,
+
+    /// Whether the tile of a tile object is flipped vertically
+    pub flip_v: bool,
+
+    /// Whether the tile of a tile object is flipped diagonally
+    pub flip_d: bool,
+}
+
+/// Represents the text and formatting of a text object
+#[table(name = tiled_object_text, public)]
+#[derive(Clone, Debug)]
+pub struct TiledObjectText {
+    /// Reference to the text object (one row per object)
+    #[primary_key]
+    pub object_id: u64,
+
+    /// Text content
+    pub text: String,
+
+    /// Font family
+    pub font_family: String,
+
+    /// Font size in pixels
+    pub pixel_size: u32,
+
+    /// Whether the text wraps at the object's width
+    pub wrap: bool,
+
+    /// Text color in `#rrggbbaa` form
+    pub color: String,
+
+    /// Whether the text is bold
+    pub bold: bool,
+
+    /// Whether the text is italic
+    pub italic: bool,
+
+    /// Whether the text is underlined
+    pub underline: bool,
+
+    /// Whether the text is struck out
+    pub strikeout: bool,
+
+    /// Whether kerning is used
+    pub kerning: bool,
+
+    /// Horizontal alignment (left, center, right, justify)
+    pub halign: String,
+
+    /// Vertical alignment (top, center, bottom)
+    pub valign: String,
 }
 
 /// Represents custom properties on any Tiled element
@@ -215,7 +635,8 @@ pub struct TiledProperty {
     #[primary_key]
     pub property_id: u64,
 
-    /// Type of parent element (map, layer, tile, object, tileset)
+    /// Type of parent element (map, layer, object, tileset, tileset_tile, tile_collision,
+    /// wang_set, wang_color, or property for the members of a class property)
     pub parent_type: String,
 
     /// ID of the parent element
@@ -228,8 +649,52 @@ pub struct TiledProperty {
     /// Property value (stored as string, parse as needed)
     pub value: String,
 
-    /// Property type (string, int, float, bool, color, file)
+    /// Property type (string, int, float, bool, color, file, object, class)
     pub value_type: String,
+
+    /// Name of the custom type of class and enum properties (Tiled's `propertytype`), empty
+    /// otherwise. Class members are the properties whose parent is this one.
+    pub property_type: String,
+}
+
+/// Represents a Tiled world (`.world` file) grouping several maps
+#[table(name = tiled_world, public)]
+#[derive(Clone, Debug)]
+pub struct TiledWorld {
+    /// Unique identifier for the world
+    #[primary_key]
+    pub world_id: u32,
+
+    /// User-defined name for this world
+    pub name: String,
+}
+
+/// Represents the placement of a map in a world
+#[table(name = tiled_world_map, public)]
+#[derive(Clone, Debug)]
+pub struct TiledWorldMap {
+    /// Reference to the map (one row per map; each world loads its own copy of its maps)
+    #[primary_key]
+    pub map_id: u32,
+
+    /// Reference to the parent world
+    #[index(btree)]
+    pub world_id: u32,
+
+    /// Path of the map file, relative to the world file
+    pub file_name: String,
+
+    /// World-space X coordinate of the map's top-left corner, in pixels
+    pub x: i32,
+
+    /// World-space Y coordinate of the map's top-left corner, in pixels
+    pub y: i32,
+
+    /// Width of the area the map covers, in pixels
+    pub width: u32,
+
+    /// Height of the area the map covers, in pixels
+    pub height: u32,
 }
 
 // ============================================================================
@@ -274,7 +739,37 @@ pub fn load_tmx_map(ctx: &ReducerContext, map_name: &str, tmx_path: &str) -> Res
         .load_tmx_map(tmx_path)
         .map_err(|e| format!("Failed to load TMX file: {e}"))?;
 
-    load_tmx_map_internal(ctx, map_name, map)
+    // The tiled crate doesn't expose firstgid or every map attribute, so read them from
+    // the file directly
+    let tmx_content =
+        std::fs::read_to_string(tmx_path).map_err(|e| format!("Failed to read TMX file: {e}"))?;
+    let first_gids = read_tileset_first_gids(&tmx_content)?;
+    let map_header = read_map_header(&tmx_content)?;
+
+    // Same for tileset attributes like the grid and allowed transformations
+    let mut tileset_headers = Vec::new();
+    for (tileset_index, tileset) in map.tilesets().iter().enumerate() {
+        let header = if tileset.source == map.source {
+            read_tileset_header(&tmx_content, tileset_index)?
+        } else {
+            let tsx_content = std::fs::read_to_string(&tileset.source)
+                .map_err(|e| format!("Failed to read TSX file: {e}"))?;
+            TiledTileset {
+                source_hash: Some(content_hash(&tsx_content)),
+                ..read_tileset_header(&tsx_content, 0)?
+            }
+        };
+        tileset_headers.push(header);
+    }
+
+    load_tmx_map_internal(
+        ctx,
+        map_name,
+        map,
+        map_header,
+        &tileset_headers,
+        &first_gids,
+    )
 }
 
 /// Load a TMX map from a string into SpacetimeDB tables
@@ -292,6 +787,9 @@ pub fn load_tmx_map(ctx: &ReducerContext, map_name: &str, tmx_path: &str) -> Res
 ///
 /// Returns `Ok(map_id)` on success or an error message on failure
 ///
+/// External tilesets (`<tileset source="..."/>`) can't be read without a filesystem and
+/// are stored without their metadata; use [`load_tmx_map_with_sources`] to supply them.
+///
 /// # Example
 ///
 /// ```rust,no_run
@@ -301,7 +799,7 @@ pub fn load_tmx_map(ctx: &ReducerContext, map_name: &str, tmx_path: &str) -> Res
 /// #[reducer(init)]
 /// pub fn init(ctx: &ReducerContext) -> Result<(), String> {
 ///     // Embed the TMX file at compile time
-///     const MAP_DATA: &str = include_str!("../assets/demo_map.tmx");
+///     const MAP_DATA: &str = include_str!("../examples/simple_game/assets/demo_map.tmx");
 ///     load_tmx_map_from_str(ctx, "demo", MAP_DATA)?;
 ///     Ok(())
 /// }
@@ -311,447 +809,1277 @@ pub fn load_tmx_map_from_str(
     map_name: &str,
     tmx_content: &str,
 ) -> Result<u32, String> {
-    use quick_xml::events::Event;
-    use quick_xml::Reader;
+    load_tmx_map_with_sources(ctx, map_name, "", tmx_content, &HashMap::new())
+}
 
+/// Load a TMX map from a string, reading external tilesets and templates from in-memory sources
+///
+/// `sources` maps file paths to their content, and `map_path` is the path of the TMX
+/// file itself. All of them are relative to the same base directory, such as the
+/// project's asset folder. `./` and `..` segments are normalized, so for a map at
+/// `"maps/town.tmx"` the key `"tilesets/terrain.tsx"` matches its
+/// `<tileset source="../tilesets/./terrain.tsx"/>`.
+///
+/// External tilesets are identified by that normalized path, which is also what
+/// [`load_tmx_map`] stores for maps read from disk. An absolute path keeps its leading
+/// `/`. A tileset a previously loaded map stored from the same path and with the same
+/// content is shared through `definition_tileset_id` instead of being stored again. An
+/// edited file is stored as a new tileset, and maps loaded earlier keep the old version.
+/// Tilesets missing from `sources` are stored without their metadata.
+///
+/// Objects with a `template` attribute are merged with their `.tx` file like Tiled does:
+/// attributes, properties and text set on the object win, and everything else comes from
+/// the template. Paths inside a template are relative to the template.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use std::collections::HashMap;
+/// use spacetimedb::{reducer, ReducerContext};
+/// use spacetime_tiled::load_tmx_map_with_sources;
+///
+/// #[reducer]
+/// pub fn upload_maps(
+///     ctx: &ReducerContext,
+///     town_tmx: String,
+///     forest_tmx: String,
+///     terrain_tsx: String,
+/// ) -> Result<(), String> {
+///     let sources = HashMap::from([("terrain.tsx".to_string(), terrain_tsx)]);
+///     load_tmx_map_with_sources(ctx, "town", "town.tmx", &town_tmx, &sources)?;
+///     // Shares the terrain tileset stored for the town
+///     load_tmx_map_with_sources(ctx, "forest", "forest.tmx", &forest_tmx, &sources)?;
+///     Ok(())
+/// }
+/// ```
+pub fn load_tmx_map_with_sources(
+    ctx: &ReducerContext,
+    map_name: &str,
+    map_path: &str,
+    tmx_content: &str,
+    sources: &HashMap<String, String>,
+) -> Result<u32, String> {
     log::info!("Parsing TMX map '{map_name}' from string");
 
-    let mut reader = Reader::from_str(tmx_content);
-    reader.config_mut().trim_text(true);
+    let mut rows = parse_tmx_rows(
+        map_path,
+        tmx_content,
+        sources,
+        FirstIds::from_db(ctx)?,
+        |source, source_hash| find_external_tileset(ctx, source, source_hash),
+    )?;
+    rows.map.name = map_name.to_string();
 
-    // Generate map ID first
-    let map_id = generate_map_id(ctx)?;
+    let map_id = rows.map.map_id;
+    insert_map_rows(ctx, rows)?;
+
+    log::info!("Successfully loaded map '{map_name}' from string");
+    Ok(map_id)
+}
+
+/// Read a TMX map, and the external tilesets and templates it uses, into table rows
+///
+/// This is the parsing half of [`load_tmx_map_with_sources`]. It doesn't touch the
+/// database: row IDs continue from `ids`, and `stored_tileset` finds the external tileset
+/// another map stored from a normalized path and content hash. The map's `name` is left
+/// empty for the caller to fill in.
+fn parse_tmx_rows(
+    map_path: &str,
+    tmx_content: &str,
+    sources: &HashMap<String, String>,
+    ids: FirstIds,
+    stored_tileset: impl Fn(&str, u64) -> Option<TiledTileset>,
+) -> Result<MapRows, String> {
+    use quick_xml::events::Event;
+
+    // Tilesets and templates are relative to the map
+    let map_dir = parent_dir(map_path);
 
-    // Map metadata
-    let mut width = 0u32;
-    let mut height = 0u32;
-    let mut tile_width = 0u32;
-    let mut tile_height = 0u32;
-    let mut orientation = String::from("orthogonal");
-    let mut background_color: Option<String> = None;
+    // The TMX file, followed by any external files it references while they're being read
+    let mut readers = vec![xml_reader(tmx_content)];
+
+    // Map metadata is filled in from the <map> element
+    let mut rows = MapRows::new(parse_map(&quick_xml::events::BytesStart::new("map"))?, ids);
+    let map_id = ids.map;
 
     // Current layer data
     let mut current_layer_id: Option<u32> = None;
+    // Open <group> layers, innermost last
+    let mut group_stack: Vec<u32> = Vec::new();
     let mut current_layer_type = String::new();
     let mut in_data_element = false;
+    let mut data_encoding: Option<String> = None;
+    let mut data_compression: Option<String> = None;
+    // Origin and width of the chunk being read (infinite maps only)
+    let mut current_chunk: Option<(i32, i32, u32)> = None;
+    // GIDs of `<data>` without an encoding, one `<tile>` element per cell, stored at
+    // the end of the chunk or data element
+    let mut xml_tiles: Vec<u32> = Vec::new();
+    let mut current_bounds: Option<TileBounds> = None;
+
+    // Property tracking. Every open element pushes the (parent_type, parent_id) its
+    // `<properties>` children belong to, or None if it can't own properties.
+    let mut owner_stack: Vec<Option<(&'static str, u64)>> = Vec::new();
+    let mut pending_property: Option<TiledProperty> = None;
+    // Text object payload, inserted once its content has been read
+    let mut pending_text: Option<TiledObjectText> = None;
+    let mut current_tileset_id: Option<u32> = None;
+    // First GID and source of the external tileset whose .tsx file is being read
+    let mut pending_external_tileset: Option<(u32, String, u64)> = None;
+
+    // Animation tracking: the animated tile and the index of the next frame
+    let mut current_animation: Option<(u64, u32)> = None;
+    // Tileset tile whose collision <objectgroup> is being read
+    let mut current_collision_tile: Option<u64> = None;
+    // Wang set being read and the number of colors seen in it so far
+    let mut current_wang_set: Option<(u32, u32)> = None;
+    // Indexes of the set's pre-1.5 <wangedgecolor>s and <wangcornercolor>s
+    let mut legacy_wang_colors: (Vec<u8>, Vec<u8>) = (Vec::new(), Vec::new());
+
+    // Object templates by path (None if missing from `sources`), and the element
+    // created from a template whose properties and text are applied at its </object>
+    let mut templates: HashMap<String, Option<ObjectTemplate>> = HashMap::new();
+    let mut current_template: Option<((&'static str, u64), String)> = None;
 
     // Tileset tracking
     let mut tileset_counter = 0u32;
 
     let mut buf = Vec::new();
 
-    loop {
+    while let Some(reader) = readers.last_mut() {
         match reader.read_event_into(&mut buf) {
-            Ok(Event::Start(e)) | Ok(Event::Empty(e)) => {
+            Ok(Event::Start(e)) => {
+                let mut owner = None;
+
                 match e.name().as_ref() {
                     b"map" => {
-                        // Parse map attributes
-                        for attr in e.attributes() {
-                            let attr =
-                                attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
-                            match attr.key.as_ref() {
-                                b"width" => {
-                                    width = std::str::from_utf8(&attr.value)
-                                        .unwrap()
-                                        .parse()
-                                        .unwrap_or(0)
-                                }
-                                b"height" => {
-                                    height = std::str::from_utf8(&attr.value)
-                                        .unwrap()
-                                        .parse()
-                                        .unwrap_or(0)
+                        owner = Some(("map", map_id as u64));
+                        rows.map = TiledMap {
+                            map_id,
+                            ..parse_map(&e)?
+                        };
+                    }
+                    b"tileset" => {
+                        let tileset = parse_tileset(&e)?;
+
+                        // A reference to an external tileset: share it if another map already
+                        // stored this version, otherwise read the .tsx file in place of this
+                        // element
+                        if let Some(source) = tileset.source.as_deref() {
+                            let source = resolve_path(map_dir, source);
+
+                            if let Some(tsx_content) = find_source(sources, &source) {
+                                let source_hash = content_hash(tsx_content);
+                                // This map may have stored it already
+                                let definition = rows
+                                    .tilesets
+                                    .iter()
+                                    .find(|stored| {
+                                        is_external_definition(stored, &source, source_hash)
+                                    })
+                                    .cloned()
+                                    .or_else(|| stored_tileset(&source, source_hash));
+
+                                if let Some(definition) = definition {
+                                    rows.tilesets.push(TiledTileset {
+                                        tileset_id: rows.next_tileset_id(),
+                                        map_id,
+                                        tileset_index: tileset_counter,
+                                        first_gid: tileset.first_gid,
+                                        ..definition
+                                    });
+                                    tileset_counter += 1;
+                                } else {
+                                    pending_external_tileset =
+                                        Some((tileset.first_gid, source, source_hash));
+                                    readers.push(xml_reader(tsx_content));
                                 }
-                                b"tilewidth" => {
-                                    tile_width = std::str::from_utf8(&attr.value)
-                                        .unwrap()
-                                        .parse()
-                                        .unwrap_or(0)
+                            } else {
+                                log::warn!(
+                                    "External tileset '{source}' not found in sources, storing it without metadata"
+                                );
+                                let tileset_id = rows.next_tileset_id();
+                                rows.tilesets.push(TiledTileset {
+                                    tileset_id,
+                                    map_id,
+                                    tileset_index: tileset_counter,
+                                    tile_width: 0,
+                                    tile_height: 0,
+                                    source: Some(source),
+                                    definition_tileset_id: tileset_id,
+                                    ..tileset
+                                });
+                                tileset_counter += 1;
+                            }
+                        } else {
+                            // An embedded tileset, or the root element of an external .tsx file
+                            let (first_gid, source, source_hash) =
+                                match pending_external_tileset.take() {
+                                    Some((first_gid, source, source_hash)) => {
+                                        (first_gid, Some(source), Some(source_hash))
+                                    }
+                                    None => (tileset.first_gid, None, None),
+                                };
+
+                            let tileset_id = rows.next_tileset_id();
+                            rows.tilesets.push(TiledTileset {
+                                tileset_id,
+                                map_id,
+                                tileset_index: tileset_counter,
+                                first_gid,
+                                source,
+                                source_hash,
+                                definition_tileset_id: tileset_id,
+                                ..tileset
+                            });
+
+                            tileset_counter += 1;
+                            current_tileset_id = Some(tileset_id);
+                            owner = Some(("tileset", tileset_id as u64));
+                        }
+                    }
+                    b"tileoffset" | b"grid" | b"transformations" => {
+                        if let Some(Some(("tileset", tileset_id))) = owner_stack.last() {
+                            if let Some(tileset) = rows.tileset_mut(*tileset_id as u32) {
+                                parse_tileset_child(&e, tileset)?;
+                            }
+                        }
+                    }
+                    b"image" => {
+                        let (image_source, image_width, image_height) = parse_image(&e)?;
+
+                        match owner_stack.last() {
+                            Some(Some(("tileset", tileset_id))) => {
+                                if let Some(tileset) = rows.tileset_mut(*tileset_id as u32) {
+                                    tileset.image_source = image_source;
+                                    tileset.image_width = image_width;
+                                    tileset.image_height = image_height;
                                 }
-                                b"tileheight" => {
-                                    tile_height = std::str::from_utf8(&attr.value)
-                                        .unwrap()
-                                        .parse()
-                                        .unwrap_or(0)
+                            }
+                            Some(Some(("tileset_tile", tileset_tile_id))) => {
+                                let tileset_tile_id = *tileset_tile_id;
+                                if let Some(tile) = rows
+                                    .tileset_tiles
+                                    .iter_mut()
+                                    .find(|tile| tile.tileset_tile_id == tileset_tile_id)
+                                {
+                                    // Without a sub-rectangle the tile uses the whole image
+                                    tile.image_rect_x = tile.image_rect_x.or(Some(0));
+                                    tile.image_rect_y = tile.image_rect_y.or(Some(0));
+                                    tile.image_rect_width = tile.image_rect_width.or(image_width);
+                                    tile.image_rect_height =
+                                        tile.image_rect_height.or(image_height);
+                                    tile.image_source = image_source;
+                                    tile.image_width = image_width;
+                                    tile.image_height = image_height;
                                 }
-                                b"orientation" => {
-                                    orientation =
-                                        std::str::from_utf8(&attr.value).unwrap().to_string()
+                            }
+                            Some(Some(("layer", layer_id))) => {
+                                let mut transparent_color = None;
+                                for attr in e.attributes() {
+                                    let attr = attr
+                                        .map_err(|e| format!("Failed to parse attribute: {e}"))?;
+                                    if attr.key.as_ref() == b"trans" {
+                                        transparent_color =
+                                            Some(normalize_color(&attr.unescape_value().map_err(
+                                                |e| format!("Failed to parse attribute: {e}"),
+                                            )?));
+                                    }
                                 }
-                                b"backgroundcolor" => {
-                                    background_color =
-                                        Some(std::str::from_utf8(&attr.value).unwrap().to_string())
+
+                                let layer_id = *layer_id as u32;
+                                if let Some(image_layer) = rows
+                                    .image_layers
+                                    .iter_mut()
+                                    .find(|image_layer| image_layer.layer_id == layer_id)
+                                {
+                                    image_layer.image_source = image_source;
+                                    image_layer.image_width = image_width;
+                                    image_layer.image_height = image_height;
+                                    image_layer.transparent_color = transparent_color;
                                 }
-                                _ => {}
                             }
+                            _ => {}
                         }
                     }
-                    b"tileset" => {
-                        // Parse tileset attributes
-                        let mut name = String::new();
-                        let mut ts_tile_width = 0u32;
-                        let mut ts_tile_height = 0u32;
-                        let mut tile_count = 0u32;
-                        let mut columns = 0u32;
+                    b"tile" if in_data_element => {
+                        xml_tiles.push(parse_data_tile(&e)?);
+                    }
+                    b"tile" => {
+                        // Tiles inside a tileset carry per-tile metadata
+                        if let Some(tileset_id) = current_tileset_id {
+                            let mut local_id = 0u32;
+                            let mut tile_type = String::new();
+                            let mut probability = 1.0f32;
+                            // Sub-rectangle of the tile's image (Tiled 1.9+)
+                            let mut image_rect_x = None;
+                            let mut image_rect_y = None;
+                            let mut image_rect_width = None;
+                            let mut image_rect_height = None;
 
-                        for attr in e.attributes() {
-                            let attr =
-                                attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
-                            match attr.key.as_ref() {
-                                b"name" => {
-                                    name = std::str::from_utf8(&attr.value).unwrap().to_string()
-                                }
-                                b"tilewidth" => {
-                                    ts_tile_width = std::str::from_utf8(&attr.value)
-                                        .unwrap()
-                                        .parse()
-                                        .unwrap_or(0)
-                                }
-                                b"tileheight" => {
-                                    ts_tile_height = std::str::from_utf8(&attr.value)
-                                        .unwrap()
-                                        .parse()
-                                        .unwrap_or(0)
-                                }
-                                b"tilecount" => {
-                                    tile_count = std::str::from_utf8(&attr.value)
-                                        .unwrap()
-                                        .parse()
-                                        .unwrap_or(0)
-                                }
-                                b"columns" => {
-                                    columns = std::str::from_utf8(&attr.value)
-                                        .unwrap()
-                                        .parse()
-                                        .unwrap_or(0)
+                            for attr in e.attributes() {
+                                let attr =
+                                    attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+                                match attr.key.as_ref() {
+                                    b"id" => {
+                                        local_id = std::str::from_utf8(&attr.value)
+                                            .unwrap()
+                                            .parse()
+                                            .unwrap_or(0)
+                                    }
+                                    // Tiled 1.9 renamed "type" to "class"
+                                    b"type" | b"class" => {
+                                        tile_type = attr
+                                            .unescape_value()
+                                            .map_err(|e| format!("Failed to parse attribute: {e}"))?
+                                            .to_string()
+                                    }
+                                    b"probability" => {
+                                        probability = std::str::from_utf8(&attr.value)
+                                            .unwrap()
+                                            .parse()
+                                            .unwrap_or(1.0)
+                                    }
+                                    b"x" => {
+                                        image_rect_x =
+                                            std::str::from_utf8(&attr.value).unwrap().parse().ok()
+                                    }
+                                    b"y" => {
+                                        image_rect_y =
+                                            std::str::from_utf8(&attr.value).unwrap().parse().ok()
+                                    }
+                                    b"width" => {
+                                        image_rect_width =
+                                            std::str::from_utf8(&attr.value).unwrap().parse().ok()
+                                    }
+                                    b"height" => {
+                                        image_rect_height =
+                                            std::str::from_utf8(&attr.value).unwrap().parse().ok()
+                                    }
+                                    _ => {}
                                 }
-                                _ => {}
                             }
-                        }
 
-                        let tileset_id = generate_tileset_id(ctx)?;
-                        ctx.db
-                            .tiled_tileset()
-                            .try_insert(TiledTileset {
+                            let tileset_tile_id = tileset_tile_key(tileset_id, local_id);
+                            rows.tileset_tiles.push(TiledTilesetTile {
+                                tileset_tile_id,
                                 tileset_id,
-                                map_id,
-                                tileset_index: tileset_counter,
-                                name,
-                                tile_width: ts_tile_width,
-                                tile_height: ts_tile_height,
-                                tile_count,
-                                columns,
+                                local_id,
+                                tile_type,
+                                probability,
                                 image_source: None,
                                 image_width: None,
                                 image_height: None,
-                            })
-                            .map_err(|e| format!("Failed to insert tileset: {e}"))?;
-
-                        tileset_counter += 1;
-                    }
-                    b"layer" => {
-                        // Parse layer attributes
-                        let mut name = String::new();
-                        let mut visible = true;
-                        let mut opacity = 1.0f32;
-                        let mut offset_x = 0i32;
-                        let mut offset_y = 0i32;
+                                image_rect_x,
+                                image_rect_y,
+                                image_rect_width,
+                                image_rect_height,
+                            });
 
-                        for attr in e.attributes() {
-                            let attr =
-                                attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
-                            match attr.key.as_ref() {
-                                b"name" => {
-                                    name = std::str::from_utf8(&attr.value).unwrap().to_string()
-                                }
-                                b"visible" => {
-                                    visible = std::str::from_utf8(&attr.value).unwrap() == "1"
-                                }
-                                b"opacity" => {
-                                    opacity = std::str::from_utf8(&attr.value)
-                                        .unwrap()
-                                        .parse()
-                                        .unwrap_or(1.0)
-                                }
-                                b"offsetx" => {
-                                    offset_x = std::str::from_utf8(&attr.value)
-                                        .unwrap()
-                                        .parse()
-                                        .unwrap_or(0)
-                                }
-                                b"offsety" => {
-                                    offset_y = std::str::from_utf8(&attr.value)
-                                        .unwrap()
-                                        .parse()
-                                        .unwrap_or(0)
-                                }
-                                _ => {}
-                            }
+                            owner = Some(("tileset_tile", tileset_tile_id));
                         }
-
-                        let layer_id = generate_layer_id(ctx)?;
-                        ctx.db
-                            .tiled_layer()
-                            .try_insert(TiledLayer {
-                                layer_id,
-                                map_id,
-                                name,
-                                layer_type: "tile".to_string(),
-                                visible,
-                                opacity,
-                                offset_x,
-                                offset_y,
-                                z_order: layer_id,
-                            })
-                            .map_err(|e| format!("Failed to insert layer: {e}"))?;
-
-                        current_layer_id = Some(layer_id);
-                        current_layer_type = "tile".to_string();
                     }
-                    b"objectgroup" => {
-                        // Parse object group attributes
-                        let mut name = String::new();
-                        let mut visible = true;
-                        let mut opacity = 1.0f32;
-                        let mut offset_x = 0i32;
-                        let mut offset_y = 0i32;
+                    b"wangset" => {
+                        if let Some(tileset_id) = current_tileset_id {
+                            let mut name = String::new();
+                            let mut wang_type = String::from("mixed");
+                            let mut tile_id = None;
 
-                        for attr in e.attributes() {
-                            let attr =
-                                attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
-                            match attr.key.as_ref() {
-                                b"name" => {
-                                    name = std::str::from_utf8(&attr.value).unwrap().to_string()
-                                }
-                                b"visible" => {
-                                    visible = std::str::from_utf8(&attr.value).unwrap() == "1"
-                                }
-                                b"opacity" => {
-                                    opacity = std::str::from_utf8(&attr.value)
-                                        .unwrap()
-                                        .parse()
-                                        .unwrap_or(1.0)
-                                }
-                                b"offsetx" => {
-                                    offset_x = std::str::from_utf8(&attr.value)
-                                        .unwrap()
-                                        .parse()
-                                        .unwrap_or(0)
-                                }
-                                b"offsety" => {
-                                    offset_y = std::str::from_utf8(&attr.value)
-                                        .unwrap()
-                                        .parse()
-                                        .unwrap_or(0)
+                            for attr in e.attributes() {
+                                let attr =
+                                    attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+                                match attr.key.as_ref() {
+                                    b"name" => {
+                                        name = attr
+                                            .unescape_value()
+                                            .map_err(|e| format!("Failed to parse attribute: {e}"))?
+                                            .to_string()
+                                    }
+                                    b"type" => {
+                                        wang_type = attr
+                                            .unescape_value()
+                                            .map_err(|e| format!("Failed to parse attribute: {e}"))?
+                                            .to_string()
+                                    }
+                                    // -1 means no representative tile
+                                    b"tile" => {
+                                        tile_id =
+                                            std::str::from_utf8(&attr.value).unwrap().parse().ok()
+                                    }
+                                    _ => {}
                                 }
-                                _ => {}
                             }
-                        }
 
-                        let layer_id = generate_layer_id(ctx)?;
-                        ctx.db
-                            .tiled_layer()
-                            .try_insert(TiledLayer {
-                                layer_id,
-                                map_id,
+                            let wang_set_id = rows.next_wang_set_id();
+                            rows.wang_sets.push(TiledWangSet {
+                                wang_set_id,
+                                tileset_id,
                                 name,
-                                layer_type: "object".to_string(),
-                                visible,
-                                opacity,
-                                offset_x,
-                                offset_y,
-                                z_order: layer_id,
-                            })
-                            .map_err(|e| format!("Failed to insert layer: {e}"))?;
+                                wang_type,
+                                tile_id,
+                            });
 
-                        current_layer_id = Some(layer_id);
-                        current_layer_type = "object".to_string();
+                            current_wang_set = Some((wang_set_id, 0));
+                            legacy_wang_colors = (Vec::new(), Vec::new());
+                            owner = Some(("wang_set", wang_set_id as u64));
+                        }
                     }
-                    b"object" => {
-                        if let Some(layer_id) = current_layer_id {
-                            // Parse object attributes
+                    // Tiled before 1.5 numbered edge and corner colors separately
+                    b"wangcolor" | b"wangedgecolor" | b"wangcornercolor" => {
+                        if let Some((wang_set_id, color_count)) = current_wang_set.as_mut() {
                             let mut name = String::new();
-                            let mut obj_type = String::new();
-                            let mut x = 0.0f32;
-                            let mut y = 0.0f32;
-                            let mut width = 0.0f32;
-                            let mut height = 0.0f32;
-                            let mut rotation = 0.0f32;
-                            let mut visible = true;
+                            let mut color = String::new();
+                            let mut tile_id = None;
+                            let mut probability = 1.0f32;
 
                             for attr in e.attributes() {
                                 let attr =
                                     attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
                                 match attr.key.as_ref() {
                                     b"name" => {
-                                        name = std::str::from_utf8(&attr.value).unwrap().to_string()
+                                        name = attr
+                                            .unescape_value()
+                                            .map_err(|e| format!("Failed to parse attribute: {e}"))?
+                                            .to_string()
                                     }
-                                    b"type" => {
-                                        obj_type =
-                                            std::str::from_utf8(&attr.value).unwrap().to_string()
+                                    b"color" => {
+                                        color = normalize_color(&attr.unescape_value().map_err(
+                                            |e| format!("Failed to parse attribute: {e}"),
+                                        )?)
                                     }
-                                    b"x" => {
-                                        x = std::str::from_utf8(&attr.value)
-                                            .unwrap()
-                                            .parse()
-                                            .unwrap_or(0.0)
+                                    b"tile" => {
+                                        tile_id =
+                                            std::str::from_utf8(&attr.value).unwrap().parse().ok()
                                     }
-                                    b"y" => {
-                                        y = std::str::from_utf8(&attr.value)
+                                    b"probability" => {
+                                        probability = std::str::from_utf8(&attr.value)
                                             .unwrap()
                                             .parse()
-                                            .unwrap_or(0.0)
+                                            .unwrap_or(1.0)
                                     }
-                                    b"width" => {
-                                        width = std::str::from_utf8(&attr.value)
+                                    _ => {}
+                                }
+                            }
+
+                            *color_count += 1;
+                            match e.name().as_ref() {
+                                b"wangedgecolor" => legacy_wang_colors.0.push(*color_count as u8),
+                                b"wangcornercolor" => legacy_wang_colors.1.push(*color_count as u8),
+                                _ => {}
+                            }
+                            let wang_color_id = rows.next_wang_color_id();
+                            rows.wang_colors.push(TiledWangColor {
+                                wang_color_id,
+                                wang_set_id: *wang_set_id,
+                                color_index: *color_count,
+                                name,
+                                color,
+                                tile_id,
+                                probability,
+                            });
+
+                            owner = Some(("wang_color", wang_color_id as u64));
+                        }
+                    }
+                    b"wangtile" => {
+                        if let Some((wang_set_id, _)) = current_wang_set {
+                            let mut local_id = 0u32;
+                            let mut wang_id = Vec::new();
+
+                            for attr in e.attributes() {
+                                let attr =
+                                    attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+                                match attr.key.as_ref() {
+                                    b"tileid" => {
+                                        local_id = std::str::from_utf8(&attr.value)
                                             .unwrap()
                                             .parse()
-                                            .unwrap_or(0.0)
+                                            .unwrap_or(0)
                                     }
-                                    b"height" => {
-                                        height = std::str::from_utf8(&attr.value)
+                                    b"wangid" => {
+                                        wang_id = parse_wang_id(
+                                            std::str::from_utf8(&attr.value).unwrap(),
+                                            &legacy_wang_colors,
+                                        )?
+                                    }
+                                    _ => {}
+                                }
+                            }
+
+                            rows.wang_tiles.push(TiledWangTile {
+                                wang_tile_id: rows.next_wang_tile_id(),
+                                wang_set_id,
+                                local_id,
+                                wang_id,
+                            });
+                        }
+                    }
+                    b"animation" => {
+                        if let Some(Some(("tileset_tile", tileset_tile_id))) = owner_stack.last() {
+                            current_animation = Some((*tileset_tile_id, 0));
+                        }
+                    }
+                    b"frame" => {
+                        if let Some((tileset_tile_id, frame_index)) = current_animation.as_mut() {
+                            let mut frame_tile_id = 0u32;
+                            let mut duration_ms = 0u32;
+
+                            for attr in e.attributes() {
+                                let attr =
+                                    attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+                                match attr.key.as_ref() {
+                                    b"tileid" => {
+                                        frame_tile_id = std::str::from_utf8(&attr.value)
                                             .unwrap()
                                             .parse()
-                                            .unwrap_or(0.0)
+                                            .unwrap_or(0)
                                     }
-                                    b"rotation" => {
-                                        rotation = std::str::from_utf8(&attr.value)
+                                    b"duration" => {
+                                        duration_ms = std::str::from_utf8(&attr.value)
                                             .unwrap()
                                             .parse()
-                                            .unwrap_or(0.0)
-                                    }
-                                    b"visible" => {
-                                        visible = std::str::from_utf8(&attr.value).unwrap() == "1"
+                                            .unwrap_or(0)
                                     }
                                     _ => {}
                                 }
                             }
 
-                            let object_id = generate_object_id(ctx)?;
-                            let shape = if width == 0.0 && height == 0.0 {
-                                "point"
-                            } else {
-                                "rectangle"
-                            };
+                            rows.animations.push(TiledTileAnimation {
+                                frame_id: rows.next_animation_id(),
+                                ..animation_frame(
+                                    *tileset_tile_id,
+                                    *frame_index,
+                                    frame_tile_id,
+                                    duration_ms,
+                                )
+                            });
+                            *frame_index += 1;
+                        }
+                    }
+                    b"group" => {
+                        let layer_id = rows.next_layer_id();
+                        rows.layers.push(TiledLayer {
+                            layer_id,
+                            map_id,
+                            z_order: rows.layers.len() as u32,
+                            parent_layer_id: group_stack.last().copied(),
+                            ..parse_layer(&e, "group")?
+                        });
+
+                        group_stack.push(layer_id);
+                        owner = Some(("layer", layer_id as u64));
+                    }
+                    b"layer" => {
+                        let layer_id = rows.next_layer_id();
+                        rows.layers.push(TiledLayer {
+                            layer_id,
+                            map_id,
+                            z_order: rows.layers.len() as u32,
+                            parent_layer_id: group_stack.last().copied(),
+                            ..parse_layer(&e, "tile")?
+                        });
 
-                            ctx.db
-                                .tiled_object()
-                                .try_insert(TiledObject {
-                                    object_id,
-                                    layer_id,
-                                    name,
-                                    obj_type,
-                                    x,
-                                    y,
-                                    width,
-                                    height,
-                                    rotation,
-                                    visible,
-                                    shape: shape.to_string(),
-                                })
-                                .map_err(|e| format!("Failed to insert object: {e}"))?;
+                        current_layer_id = Some(layer_id);
+                        current_layer_type = "tile".to_string();
+                        current_bounds = None;
+                        owner = Some(("layer", layer_id as u64));
+                    }
+                    b"objectgroup"
+                        if matches!(owner_stack.last(), Some(Some(("tileset_tile", _)))) =>
+                    {
+                        // Collision shapes of a tileset tile, not a map layer. Its objects own
+                        // their properties as `tile_collision` rows; the group itself owns
+                        // none, since the tiled crate and TMJ loader drop them too
+                        if let Some(Some((_, tileset_tile_id))) = owner_stack.last() {
+                            current_collision_tile = Some(*tileset_tile_id);
                         }
                     }
-                    b"data" => {
-                        in_data_element = true;
+                    b"imagelayer" => {
+                        let layer_id = rows.next_layer_id();
+                        rows.layers.push(TiledLayer {
+                            layer_id,
+                            map_id,
+                            z_order: rows.layers.len() as u32,
+                            parent_layer_id: group_stack.last().copied(),
+                            ..parse_layer(&e, "image")?
+                        });
+
+                        let mut repeat_x = false;
+                        let mut repeat_y = false;
+                        for attr in e.attributes() {
+                            let attr =
+                                attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+                            match attr.key.as_ref() {
+                                b"repeatx" => {
+                                    repeat_x = std::str::from_utf8(&attr.value).unwrap() == "1"
+                                }
+                                b"repeaty" => {
+                                    repeat_y = std::str::from_utf8(&attr.value).unwrap() == "1"
+                                }
+                                _ => {}
+                            }
+                        }
+
+                        // The <image> child fills in the image, if the layer has one
+                        rows.image_layers.push(TiledImageLayer {
+                            layer_id,
+                            image_source: None,
+                            image_width: None,
+                            image_height: None,
+                            transparent_color: None,
+                            repeat_x,
+                            repeat_y,
+                        });
+
+                        current_layer_id = Some(layer_id);
+                        current_layer_type = "image".to_string();
+                        owner = Some(("layer", layer_id as u64));
                     }
-                    _ => {}
-                }
-            }
-            Ok(Event::Text(e)) => {
-                if in_data_element && current_layer_type == "tile" {
-                    if let Some(layer_id) = current_layer_id {
-                        // Parse CSV tile data
-                        let text = e.unescape().unwrap().to_string();
-                        let tiles: Vec<u32> = text
-                            .split(',')
-                            .filter_map(|s| s.trim().parse().ok())
-                            .collect();
-
-                        // Insert tiles
-                        for (idx, gid_with_flags) in tiles.iter().enumerate() {
-                            if *gid_with_flags == 0 {
-                                continue; // Skip empty tiles
+                    b"objectgroup" => {
+                        let layer_id = rows.next_layer_id();
+                        rows.layers.push(TiledLayer {
+                            layer_id,
+                            map_id,
+                            z_order: rows.layers.len() as u32,
+                            parent_layer_id: group_stack.last().copied(),
+                            ..parse_layer(&e, "object")?
+                        });
+
+                        current_layer_id = Some(layer_id);
+                        current_layer_type = "object".to_string();
+                        owner = Some(("layer", layer_id as u64));
+                    }
+                    b"object" => {
+                        let mut template_path = None;
+                        for attr in e.attributes() {
+                            let attr =
+                                attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+                            if attr.key.as_ref() == b"template" {
+                                template_path = Some(resolve_path(
+                                    map_dir,
+                                    &attr
+                                        .unescape_value()
+                                        .map_err(|e| format!("Failed to parse attribute: {e}"))?,
+                                ));
+                            }
+                        }
+
+                        if let Some(path) = &template_path {
+                            if !templates.contains_key(path) {
+                                let template = match find_source(sources, path) {
+                                    Some(content) => {
+                                        Some(parse_template(path, content, |source| {
+                                            rows.tilesets
+                                                .iter()
+                                                .find(|tileset| {
+                                                    tileset.source.as_deref() == Some(source)
+                                                })
+                                                .map(|tileset| tileset.first_gid)
+                                        })?)
+                                    }
+                                    None => {
+                                        log::warn!(
+                                            "Object template '{path}' not found in sources; objects using it only get their own attributes"
+                                        );
+                                        None
+                                    }
+                                };
+                                templates.insert(path.clone(), template);
                             }
+                        }
 
-                            let x = (idx as u32) % width;
-                            let y = (idx as u32) / width;
+                        // Instance attributes override the template's
+                        let template = template_path
+                            .as_ref()
+                            .and_then(|path| templates.get(path))
+                            .and_then(Option::as_ref);
+                        let object = match template {
+                            Some(template) => {
+                                parse_object(&merge_template_attributes(&e, template)?)?
+                            }
+                            None => parse_object(&e)?,
+                        };
+
+                        if let Some(tileset_tile_id) = current_collision_tile {
+                            let collision_id = rows.next_collision_id();
+                            rows.collisions.push(TiledTileCollision {
+                                collision_id,
+                                tileset_tile_id,
+                                name: object.name,
+                                obj_type: object.obj_type,
+                                x: object.x,
+                                y: object.y,
+                                width: object.width,
+                                height: object.height,
+                                rotation: object.rotation,
+                                visible: object.visible,
+                                shape: object.shape,
+                                points: object.points,
+                            });
+
+                            owner = Some(("tile_collision", collision_id));
+                        } else if let Some(layer_id) = current_layer_id {
+                            let object_id = rows.next_object_id();
+                            rows.objects.push(TiledObject {
+                                object_id,
+                                layer_id,
+                                ..object
+                            });
 
-                            // Extract flip flags
-                            let flip_h = (gid_with_flags & 0x80000000) != 0;
-                            let flip_v = (gid_with_flags & 0x40000000) != 0;
-                            let flip_d = (gid_with_flags & 0x20000000) != 0;
-                            let gid = gid_with_flags & 0x1FFFFFFF;
+                            owner = Some(("object", object_id));
+                        }
 
-                            let tile_id = generate_tile_id(ctx)?;
-                            ctx.db
-                                .tiled_tile()
-                                .try_insert(TiledTile {
-                                    tile_id,
-                                    layer_id,
-                                    x,
-                                    y,
-                                    gid,
-                                    flip_h,
-                                    flip_v,
-                                    flip_d,
-                                })
-                                .map_err(|e| format!("Failed to insert tile: {e}"))?;
+                        // Shape child elements of the instance replace the template's
+                        if let (Some(owner), Some(template)) = (owner, template) {
+                            if let Some((shape, points)) = template.shape.clone() {
+                                set_object_shape(&mut rows, Some(owner), &shape, points);
+                            }
+                            current_template = template_path.map(|path| (owner, path));
                         }
                     }
-                }
-            }
-            Ok(Event::End(e)) => match e.name().as_ref() {
-                b"layer" | b"objectgroup" => {
-                    current_layer_id = None;
-                    current_layer_type.clear();
-                }
-                b"data" => {
-                    in_data_element = false;
-                }
-                _ => {}
-            },
-            Ok(Event::Eof) => break,
-            Err(e) => return Err(format!("XML parse error: {e}")),
-            _ => {}
-        }
-        buf.clear();
-    }
+                    b"text" => {
+                        let owner = owner_stack.last().copied().flatten();
+                        set_object_shape(&mut rows, owner, "text", Vec::new());
+
+                        if let Some(("object", object_id)) = owner {
+                            pending_text = Some(TiledObjectText {
+                                object_id,
+                                ..parse_text(&e)?
+                            });
+                            // Keep the whitespace around the text, like the tiled crate does
+                            reader.config_mut().trim_text(false);
+                        }
+                    }
+                    b"ellipse" | b"point" => {
+                        let shape = std::str::from_utf8(e.name().as_ref()).unwrap().to_string();
+                        set_object_shape(
+                            &mut rows,
+                            owner_stack.last().copied().flatten(),
+                            &shape,
+                            Vec::new(),
+                        );
+                    }
+                    b"polygon" | b"polyline" => {
+                        let mut points = Vec::new();
 
-    // Insert map metadata after parsing is complete
-    ctx.db
-        .tiled_map()
-        .try_insert(TiledMap {
-            map_id,
-            name: map_name.to_string(),
-            width,
-            height,
-            tile_width,
-            tile_height,
-            orientation,
-            background_color,
+                        for attr in e.attributes() {
+                            let attr =
+                                attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+                            if attr.key.as_ref() == b"points" {
+                                points = parse_points(
+                                    &attr
+                                        .unescape_value()
+                                        .map_err(|e| format!("Failed to parse attribute: {e}"))?,
+                                );
+                            }
+                        }
+
+                        let shape = if e.name().as_ref() == b"polygon" {
+                            "polygon"
+                        } else {
+                            "polyline"
+                        };
+                        set_object_shape(
+                            &mut rows,
+                            owner_stack.last().copied().flatten(),
+                            shape,
+                            points,
+                        );
+                    }
+                    b"property" => {
+                        // The element owning these properties is the parent of <properties>
+                        let parent = owner_stack
+                            .len()
+                            .checked_sub(2)
+                            .and_then(|idx| owner_stack[idx]);
+
+                        if let Some((parent_type, parent_id)) = parent {
+                            let property = TiledProperty {
+                                parent_type: parent_type.to_string(),
+                                parent_id,
+                                ..parse_property(&e)?
+                            };
+
+                            if property.value_type == "class" {
+                                // Its members are the <property> elements inside it
+                                let property_id = rows.add_property(property);
+                                owner = Some(("property", property_id));
+                            } else {
+                                // Multi-line values arrive as element text, so wait for </property>
+                                // and keep their whitespace
+                                pending_property = Some(property);
+                                reader.config_mut().trim_text(false);
+                            }
+                        }
+                    }
+                    b"data" => {
+                        in_data_element = true;
+
+                        for attr in e.attributes() {
+                            let attr =
+                                attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+                            match attr.key.as_ref() {
+                                b"encoding" => {
+                                    data_encoding = Some(
+                                        attr.unescape_value()
+                                            .map_err(|e| format!("Failed to parse attribute: {e}"))?
+                                            .to_string(),
+                                    )
+                                }
+                                b"compression" => {
+                                    data_compression = Some(
+                                        attr.unescape_value()
+                                            .map_err(|e| format!("Failed to parse attribute: {e}"))?
+                                            .to_string(),
+                                    )
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                    b"chunk" if in_data_element => {
+                        let mut chunk_x = 0i32;
+                        let mut chunk_y = 0i32;
+                        let mut chunk_width = 0u32;
+
+                        for attr in e.attributes() {
+                            let attr =
+                                attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+                            match attr.key.as_ref() {
+                                b"x" => {
+                                    chunk_x = std::str::from_utf8(&attr.value)
+                                        .unwrap()
+                                        .parse()
+                                        .unwrap_or(0)
+                                }
+                                b"y" => {
+                                    chunk_y = std::str::from_utf8(&attr.value)
+                                        .unwrap()
+                                        .parse()
+                                        .unwrap_or(0)
+                                }
+                                b"width" => {
+                                    chunk_width = std::str::from_utf8(&attr.value)
+                                        .unwrap()
+                                        .parse()
+                                        .unwrap_or(0)
+                                }
+                                _ => {}
+                            }
+                        }
+
+                        current_chunk = Some((chunk_x, chunk_y, chunk_width));
+                    }
+                    _ => {}
+                }
+
+                owner_stack.push(owner);
+            }
+            Ok(Event::Text(e)) if pending_property.is_some() => {
+                if let Some(property) = pending_property.as_mut() {
+                    let text = e
+                        .unescape()
+                        .map_err(|e| format!("Failed to read property value: {e}"))?;
+                    property.value.push_str(&text);
+                }
+            }
+            Ok(Event::Text(e)) if pending_text.is_some() => {
+                if let Some(text) = pending_text.as_mut() {
+                    let content = e
+                        .unescape()
+                        .map_err(|e| format!("Failed to read object text: {e}"))?;
+                    text.text.push_str(&content);
+                }
+            }
+            Ok(Event::Text(e)) if in_data_element && current_layer_type == "tile" => {
+                if let Some(layer_id) = current_layer_id {
+                    let text = e
+                        .unescape()
+                        .map_err(|e| format!("Failed to read tile data: {e}"))?;
+                    let tiles = decode_tile_data(
+                        &text,
+                        data_encoding.as_deref(),
+                        data_compression.as_deref(),
+                    )?;
+
+                    // Chunks are positioned in tile coordinates; finite layers start at 0,0
+                    let tiles = place_tiles(
+                        layer_id,
+                        tiles,
+                        current_chunk.unwrap_or((0, 0, rows.map.width)),
+                        &mut current_bounds,
+                    )?;
+                    rows.add_tiles(tiles);
+                }
+            }
+            Ok(Event::End(e)) => {
+                let ended_owner = owner_stack.pop().flatten();
+
+                match e.name().as_ref() {
+                    b"tile" => {
+                        if let Some(("tileset_tile", tileset_tile_id)) = ended_owner {
+                            let has_children = rows.properties.iter().any(|property| {
+                                property.parent_type == "tileset_tile"
+                                    && property.parent_id == tileset_tile_id
+                            }) || rows
+                                .collisions
+                                .iter()
+                                .any(|collision| collision.tileset_tile_id == tileset_tile_id)
+                                || rows
+                                    .animations
+                                    .iter()
+                                    .any(|frame| frame.tileset_tile_id == tileset_tile_id);
+                            rows.tileset_tiles.retain(|tile| {
+                                tile.tileset_tile_id != tileset_tile_id
+                                    || has_tile_metadata(tile, has_children)
+                            });
+                        }
+                    }
+                    b"property" => {
+                        if let Some(property) = pending_property.take() {
+                            rows.add_property(property);
+                        }
+                        reader.config_mut().trim_text(true);
+                    }
+                    b"tileset" => {
+                        current_tileset_id = None;
+                    }
+                    b"object" => {
+                        if let Some((owner, path)) = current_template.take() {
+                            if let Some(Some(template)) = templates.get(&path) {
+                                apply_template_children(&mut rows, owner.0, owner.1, template);
+                            }
+                        }
+                    }
+                    b"text" => {
+                        if let Some(text) = pending_text.take() {
+                            rows.object_texts.push(text);
+                        }
+                        reader.config_mut().trim_text(true);
+                    }
+                    b"animation" => {
+                        current_animation = None;
+                    }
+                    b"wangset" => {
+                        current_wang_set = None;
+                    }
+                    b"layer" => {
+                        if let Some(layer_id) = current_layer_id {
+                            rows.set_layer_bounds(layer_id, current_bounds.take());
+                        }
+                        current_layer_id = None;
+                        current_layer_type.clear();
+                    }
+                    b"group" => {
+                        group_stack.pop();
+                    }
+                    b"imagelayer" => {
+                        current_layer_id = None;
+                        current_layer_type.clear();
+                    }
+                    b"objectgroup" if current_collision_tile.is_some() => {
+                        current_collision_tile = None;
+                    }
+                    b"objectgroup" => {
+                        current_layer_id = None;
+                        current_layer_type.clear();
+                    }
+                    b"data" | b"chunk" => {
+                        // XML-encoded tiles are placed like decoded text data
+                        if let Some(layer_id) = current_layer_id.filter(|_| !xml_tiles.is_empty()) {
+                            let tiles = place_tiles(
+                                layer_id,
+                                std::mem::take(&mut xml_tiles),
+                                current_chunk.unwrap_or((0, 0, rows.map.width)),
+                                &mut current_bounds,
+                            )?;
+                            rows.add_tiles(tiles);
+                        }
+
+                        if e.name().as_ref() == b"chunk" {
+                            current_chunk = None;
+                        } else {
+                            in_data_element = false;
+                            data_encoding = None;
+                            data_compression = None;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Event::Eof) => {
+                // Done with an external file, carry on with the file that referenced it
+                readers.pop();
+            }
+            Err(e) => return Err(format!("XML parse error: {e}")),
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(rows)
+}
+
+/// The IDs the next row of each table gets: its row count, like the `generate_*_id` helpers
+#[derive(Clone, Copy, Debug, Default)]
+struct FirstIds {
+    map: u32,
+    layer: u32,
+    tileset: u32,
+    tile: u64,
+    object: u64,
+    property: u64,
+    animation: u64,
+    collision: u64,
+    wang_set: u32,
+    wang_color: u32,
+    wang_tile: u64,
+}
+
+impl FirstIds {
+    fn from_db(ctx: &ReducerContext) -> Result<Self, String> {
+        Ok(Self {
+            map: generate_map_id(ctx)?,
+            layer: generate_layer_id(ctx)?,
+            tileset: generate_tileset_id(ctx)?,
+            tile: generate_tile_id(ctx)?,
+            object: generate_object_id(ctx)?,
+            property: generate_property_id(ctx)?,
+            animation: generate_tile_animation_id(ctx)?,
+            collision: generate_tile_collision_id(ctx)?,
+            wang_set: generate_wang_set_id(ctx)?,
+            wang_color: generate_wang_color_id(ctx)?,
+            wang_tile: generate_wang_tile_id(ctx)?,
         })
+    }
+}
+
+/// The rows [`parse_tmx_rows`] reads from a map, in insertion order
+#[derive(Debug)]
+struct MapRows {
+    ids: FirstIds,
+    map: TiledMap,
+    layers: Vec<TiledLayer>,
+    image_layers: Vec<TiledImageLayer>,
+    tiles: Vec<TiledTile>,
+    tilesets: Vec<TiledTileset>,
+    tileset_tiles: Vec<TiledTilesetTile>,
+    animations: Vec<TiledTileAnimation>,
+    collisions: Vec<TiledTileCollision>,
+    wang_sets: Vec<TiledWangSet>,
+    wang_colors: Vec<TiledWangColor>,
+    wang_tiles: Vec<TiledWangTile>,
+    objects: Vec<TiledObject>,
+    object_texts: Vec<TiledObjectText>,
+    properties: Vec<TiledProperty>,
+}
+
+impl MapRows {
+    fn new(map: TiledMap, ids: FirstIds) -> Self {
+        Self {
+            ids,
+            map: TiledMap {
+                map_id: ids.map,
+                ..map
+            },
+            layers: Vec::new(),
+            image_layers: Vec::new(),
+            tiles: Vec::new(),
+            tilesets: Vec::new(),
+            tileset_tiles: Vec::new(),
+            animations: Vec::new(),
+            collisions: Vec::new(),
+            wang_sets: Vec::new(),
+            wang_colors: Vec::new(),
+            wang_tiles: Vec::new(),
+            objects: Vec::new(),
+            object_texts: Vec::new(),
+            properties: Vec::new(),
+        }
+    }
+
+    fn next_layer_id(&self) -> u32 {
+        self.ids.layer + self.layers.len() as u32
+    }
+
+    fn next_tileset_id(&self) -> u32 {
+        self.ids.tileset + self.tilesets.len() as u32
+    }
+
+    fn next_object_id(&self) -> u64 {
+        self.ids.object + self.objects.len() as u64
+    }
+
+    fn next_animation_id(&self) -> u64 {
+        self.ids.animation + self.animations.len() as u64
+    }
+
+    fn next_collision_id(&self) -> u64 {
+        self.ids.collision + self.collisions.len() as u64
+    }
+
+    fn next_wang_set_id(&self) -> u32 {
+        self.ids.wang_set + self.wang_sets.len() as u32
+    }
+
+    fn next_wang_color_id(&self) -> u32 {
+        self.ids.wang_color + self.wang_colors.len() as u32
+    }
+
+    fn next_wang_tile_id(&self) -> u64 {
+        self.ids.wang_tile + self.wang_tiles.len() as u64
+    }
+
+    fn tileset_mut(&mut self, tileset_id: u32) -> Option<&mut TiledTileset> {
+        self.tilesets
+            .iter_mut()
+            .find(|tileset| tileset.tileset_id == tileset_id)
+    }
+
+    /// Add tiles from [`place_tiles`], assigning them fresh IDs
+    fn add_tiles(&mut self, tiles: Vec<TiledTile>) {
+        for tile in tiles {
+            let tile_id = self.ids.tile + self.tiles.len() as u64;
+            self.tiles.push(TiledTile { tile_id, ..tile });
+        }
+    }
+
+    /// Add a property, assigning it a fresh ID
+    fn add_property(&mut self, property: TiledProperty) -> u64 {
+        let property_id = self.ids.property + self.properties.len() as u64;
+        self.properties.push(TiledProperty {
+            property_id,
+            ..property
+        });
+        property_id
+    }
+
+    /// Record the tile bounds on a layer once its tiles have been read
+    fn set_layer_bounds(&mut self, layer_id: u32, bounds: Option<TileBounds>) {
+        let Some((min_x, min_y, max_x, max_y)) = bounds else {
+            return;
+        };
+
+        if let Some(layer) = self
+            .layers
+            .iter_mut()
+            .find(|layer| layer.layer_id == layer_id)
+        {
+            layer.min_x = Some(min_x);
+            layer.min_y = Some(min_y);
+            layer.max_x = Some(max_x);
+            layer.max_y = Some(max_y);
+        }
+    }
+}
+
+/// Insert the rows read by [`parse_tmx_rows`]
+fn insert_map_rows(ctx: &ReducerContext, rows: MapRows) -> Result<(), String> {
+    ctx.db
+        .tiled_map()
+        .try_insert(rows.map)
         .map_err(|e| format!("Failed to insert map: {e}"))?;
+    for tileset in rows.tilesets {
+        ctx.db
+            .tiled_tileset()
+            .try_insert(tileset)
+            .map_err(|e| format!("Failed to insert tileset: {e}"))?;
+    }
+    for tile in rows.tileset_tiles {
+        ctx.db
+            .tiled_tileset_tile()
+            .try_insert(tile)
+            .map_err(|e| format!("Failed to insert tileset tile: {e}"))?;
+    }
+    for frame in rows.animations {
+        ctx.db
+            .tiled_tile_animation()
+            .try_insert(frame)
+            .map_err(|e| format!("Failed to insert animation frame: {e}"))?;
+    }
+    for collision in rows.collisions {
+        ctx.db
+            .tiled_tile_collision()
+            .try_insert(collision)
+            .map_err(|e| format!("Failed to insert tile collision: {e}"))?;
+    }
+    for wang_set in rows.wang_sets {
+        ctx.db
+            .tiled_wang_set()
+            .try_insert(wang_set)
+            .map_err(|e| format!("Failed to insert wang set: {e}"))?;
+    }
+    for wang_color in rows.wang_colors {
+        ctx.db
+            .tiled_wang_color()
+            .try_insert(wang_color)
+            .map_err(|e| format!("Failed to insert wang color: {e}"))?;
+    }
+    for wang_tile in rows.wang_tiles {
+        ctx.db
+            .tiled_wang_tile()
+            .try_insert(wang_tile)
+            .map_err(|e| format!("Failed to insert wang tile: {e}"))?;
+    }
+    for layer in rows.layers {
+        ctx.db
+            .tiled_layer()
+            .try_insert(layer)
+            .map_err(|e| format!("Failed to insert layer: {e}"))?;
+    }
+    for image_layer in rows.image_layers {
+        ctx.db
+            .tiled_image_layer()
+            .try_insert(image_layer)
+            .map_err(|e| format!("Failed to insert image layer: {e}"))?;
+    }
+    for tile in rows.tiles {
+        ctx.db
+            .tiled_tile()
+            .try_insert(tile)
+            .map_err(|e| format!("Failed to insert tile: {e}"))?;
+    }
+    for object in rows.objects {
+        ctx.db
+            .tiled_object()
+            .try_insert(object)
+            .map_err(|e| format!("Failed to insert object: {e}"))?;
+    }
+    for text in rows.object_texts {
+        ctx.db
+            .tiled_object_text()
+            .try_insert(text)
+            .map_err(|e| format!("Failed to insert object text: {e}"))?;
+    }
+    for property in rows.properties {
+        ctx.db
+            .tiled_property()
+            .try_insert(property)
+            .map_err(|e| format!("Failed to insert property: {e}"))?;
+    }
 
-    log::info!("Successfully loaded map '{map_name}' from string");
-    Ok(map_id)
+    Ok(())
 }
 
-/// Internal function that does the actual map loading work
-/// Shared by both load_tmx_map and load_tmx_map_from_bytes
+/// Internal function that does the actual map loading work for `tiled` crate maps
+///
+/// `map_header` and `tileset_headers` hold the `<map>` and `<tileset>` attributes the
+/// tiled crate doesn't expose, and `first_gids` the firstgid of each tileset. Both
+/// tileset slices are in the same order as `map.tilesets()`.
 fn load_tmx_map_internal(
     ctx: &ReducerContext,
     map_name: &str,
     map: tiled::Map,
+    map_header: TiledMap,
+    tileset_headers: &[TiledTileset],
+    first_gids: &[u32],
 ) -> Result<u32, String> {
     // Generate a unique map ID (simple counter-based approach)
     let map_id = generate_map_id(ctx)?;
 
     // Store the map metadata
     let orientation = format!("{:?}", map.orientation);
-    let background_color = map
-        .background_color
-        .map(|c| format!("#{:02x}{:02x}{:02x}{:02x}", c.red, c.green, c.blue, c.alpha));
+    let background_color = map.background_color.map(|c| format_color(&c));
 
     ctx.db
         .tiled_map()
@@ -764,6 +2092,10 @@ fn load_tmx_map_internal(
             tile_height: map.tile_height,
             orientation,
             background_color,
+            infinite: map.infinite(),
+            hex_side_length: map.hex_side_length.map(|length| length as u32),
+            class: map.user_type.clone().unwrap_or_default(),
+            ..map_header
         })
         .map_err(|e| format!("Failed to insert map: {e}"))?;
 
@@ -778,26 +2110,50 @@ fn load_tmx_map_internal(
     for (tileset_index, tileset) in map.tilesets().iter().enumerate() {
         let tileset_id = generate_tileset_id(ctx)?;
 
+        // Embedded tilesets have the map's path as their source. External ones are keyed by
+        // the normalized path they were read from, like in `load_tmx_map_with_sources`
+        let source = (tileset.source != map.source)
+            .then(|| resolve_path("", &tileset.source.to_string_lossy()));
+        let header = &tileset_headers[tileset_index];
+        let definition = source
+            .as_deref()
+            .zip(header.source_hash)
+            .and_then(|(source, source_hash)| find_external_tileset(ctx, source, source_hash));
+
         ctx.db
             .tiled_tileset()
             .try_insert(TiledTileset {
                 tileset_id,
                 map_id,
                 tileset_index: tileset_index as u32,
+                first_gid: first_gids.get(tileset_index).copied().unwrap_or(0),
                 name: tileset.name.clone(),
                 tile_width: tileset.tile_width,
                 tile_height: tileset.tile_height,
                 tile_count: tileset.tilecount,
                 columns: tileset.columns,
+                spacing: tileset.spacing,
+                margin: tileset.margin,
+                tile_offset_x: tileset.offset_x,
+                tile_offset_y: tileset.offset_y,
                 image_source: tileset
                     .image
                     .as_ref()
                     .map(|img| img.source.to_string_lossy().to_string()),
                 image_width: tileset.image.as_ref().map(|img| img.width as u32),
                 image_height: tileset.image.as_ref().map(|img| img.height as u32),
+                definition_tileset_id: definition
+                    .as_ref()
+                    .map_or(tileset_id, |definition| definition.tileset_id),
+                source,
+                ..header.clone()
             })
             .map_err(|e| format!("Failed to insert tileset: {e}"))?;
 
+        if definition.is_none() {
+            store_tileset_metadata(ctx, tileset_id, tileset)?;
+        }
+
         log::debug!(
             "Added tileset '{}' at index {}",
             tileset.name,
@@ -806,7 +2162,29 @@ fn load_tmx_map_internal(
     }
 
     // Store layers
-    for (layer_index, layer) in map.layers().enumerate() {
+    let mut z_order = 0;
+    store_layers(ctx, map_id, None, map.layers(), first_gids, &mut z_order)?;
+
+    // Store map properties
+    store_properties(ctx, "map", map_id as u64, &map.properties)?;
+
+    log::info!("Successfully loaded map '{map_name}'");
+
+    Ok(map_id)
+}
+
+/// Store layers and, recursively, the layers inside group layers
+///
+/// `z_order` counts the layers stored so far, so layers are numbered in drawing order.
+fn store_layers<'map>(
+    ctx: &ReducerContext,
+    map_id: u32,
+    parent_layer_id: Option<u32>,
+    layers: impl Iterator<Item = tiled::Layer<'map>>,
+    first_gids: &[u32],
+    z_order: &mut u32,
+) -> Result<(), String> {
+    for layer in layers {
         let layer_id = generate_layer_id(ctx)?;
         let layer_type = match layer.layer_type() {
             tiled::LayerType::Tiles(_) => "tile",
@@ -826,9 +2204,19 @@ fn load_tmx_map_internal(
                 opacity: layer.opacity,
                 offset_x: layer.offset_x as i32,
                 offset_y: layer.offset_y as i32,
-                z_order: layer_index as u32,
+                z_order: *z_order,
+                parent_layer_id,
+                tint_color: layer.tint_color.map(|c| format_color(&c)),
+                parallax_x: layer.parallax_x,
+                parallax_y: layer.parallax_y,
+                class: layer.user_type.clone().unwrap_or_default(),
+                min_x: None,
+                min_y: None,
+                max_x: None,
+                max_y: None,
             })
             .map_err(|e| format!("Failed to insert layer: {e}"))?;
+        *z_order += 1;
 
         log::debug!(
             "Added {} layer '{}' (id: {})",
@@ -837,42 +2225,77 @@ fn load_tmx_map_internal(
             layer_id
         );
 
-        // Store tiles if this is a tile layer
-        if let tiled::LayerType::Tiles(tile_layer) = layer.layer_type() {
-            store_tile_layer(ctx, layer_id, tile_layer)?;
-        }
-
-        // Store objects if this is an object layer
-        if let tiled::LayerType::Objects(object_layer) = layer.layer_type() {
-            store_object_layer(ctx, layer_id, object_layer)?;
+        match layer.layer_type() {
+            tiled::LayerType::Tiles(tile_layer) => {
+                let bounds = store_tile_layer(ctx, layer_id, tile_layer, first_gids)?;
+                set_layer_bounds(ctx, layer_id, bounds)?;
+            }
+            tiled::LayerType::Objects(object_layer) => {
+                store_object_layer(ctx, layer_id, object_layer, first_gids)?;
+            }
+            tiled::LayerType::Group(group_layer) => {
+                store_layers(
+                    ctx,
+                    map_id,
+                    Some(layer_id),
+                    group_layer.layers(),
+                    first_gids,
+                    z_order,
+                )?;
+            }
+            tiled::LayerType::Image(image_layer) => {
+                let image = image_layer.image.as_ref();
+                ctx.db
+                    .tiled_image_layer()
+                    .try_insert(TiledImageLayer {
+                        layer_id,
+                        image_source: image.map(|img| img.source.to_string_lossy().to_string()),
+                        image_width: image.map(|img| img.width as u32),
+                        image_height: image.map(|img| img.height as u32),
+                        transparent_color: image
+                            .and_then(|img| img.transparent_colour)
+                            .map(|c| format_color(&c)),
+                        repeat_x: image_layer.repeat_x,
+                        repeat_y: image_layer.repeat_y,
+                    })
+                    .map_err(|e| format!("Failed to insert image layer: {e}"))?;
+            }
         }
 
         // Store layer properties
         store_properties(ctx, "layer", layer_id as u64, &layer.properties)?;
     }
 
-    // Store map properties
-    store_properties(ctx, "map", map_id as u64, &map.properties)?;
-
-    log::info!("Successfully loaded map '{map_name}'");
-
-    Ok(map_id)
+    Ok(())
 }
 
 /// Store tiles from a tile layer
+///
+/// Returns the bounding box of the stored tiles, if any.
 fn store_tile_layer(
     ctx: &ReducerContext,
     layer_id: u32,
     tile_layer: tiled::TileLayer,
-) -> Result<(), String> {
+    first_gids: &[u32],
+) -> Result<Option<TileBounds>, String> {
+    // The tiled crate hands out tileset-local IDs; convert them back to GIDs
+    let gid_of = |tile: &tiled::LayerTile| {
+        first_gids
+            .get(tile.tileset_index())
+            .map_or(tile.id(), |first_gid| first_gid + tile.id())
+    };
+
+    let mut bounds = None;
+
     match tile_layer {
         tiled::TileLayer::Finite(finite_layer) => {
             let mut tile_count = 0;
 
-            for y in 0..finite_layer.height() {
-                for x in 0..finite_layer.width() {
-                    if let Some(tile) = finite_layer.get_tile(x as i32, y as i32) {
+            for y in 0..finite_layer.height() as i32 {
+                for x in 0..finite_layer.width() as i32 {
+                    if let Some(tile) = finite_layer.get_tile(x, y) {
                         let tile_id = generate_tile_id(ctx)?;
+                        extend_bounds(&mut bounds, x, y);
 
                         ctx.db
                             .tiled_tile()
@@ -881,7 +2304,7 @@ fn store_tile_layer(
                                 layer_id,
                                 x,
                                 y,
-                                gid: tile.id(),
+                                gid: gid_of(&tile),
                                 flip_h: tile.flip_h,
                                 flip_v: tile.flip_v,
                                 flip_d: tile.flip_d,
@@ -901,13 +2324,14 @@ fn store_tile_layer(
             // Infinite layers use chunks
             for (coords, chunk) in infinite_layer.chunks() {
                 // ChunkData has const WIDTH and HEIGHT (always 16x16)
-                for y in 0..16u32 {
-                    for x in 0..16u32 {
-                        if let Some(tile) = chunk.get_tile(x as i32, y as i32) {
+                for y in 0..16i32 {
+                    for x in 0..16i32 {
+                        if let Some(tile) = chunk.get_tile(x, y) {
                             let tile_id = generate_tile_id(ctx)?;
                             // Calculate world position
-                            let world_x = (coords.0 * 16 + x as i32) as u32;
-                            let world_y = (coords.1 * 16 + y as i32) as u32;
+                            let world_x = coords.0 * 16 + x;
+                            let world_y = coords.1 * 16 + y;
+                            extend_bounds(&mut bounds, world_x, world_y);
 
                             ctx.db
                                 .tiled_tile()
@@ -916,7 +2340,7 @@ fn store_tile_layer(
                                     layer_id,
                                     x: world_x,
                                     y: world_y,
-                                    gid: tile.id(),
+                                    gid: gid_of(&tile),
                                     flip_h: tile.flip_h,
                                     flip_v: tile.flip_v,
                                     flip_d: tile.flip_d,
@@ -933,6 +2357,108 @@ fn store_tile_layer(
         }
     }
 
+    Ok(bounds)
+}
+
+/// Insert decoded tile data, row by row from `(origin_x, origin_y, row_width)`
+///
+/// Empty tiles (GID 0) are skipped and every stored tile grows `bounds`.
+fn store_tiles(
+    ctx: &ReducerContext,
+    layer_id: u32,
+    tiles: Vec<u32>,
+    origin: (i32, i32, u32),
+    bounds: &mut Option<TileBounds>,
+) -> Result<(), String> {
+    for tile in place_tiles(layer_id, tiles, origin, bounds)? {
+        let tile_id = generate_tile_id(ctx)?;
+        ctx.db
+            .tiled_tile()
+            .try_insert(TiledTile { tile_id, ..tile })
+            .map_err(|e| format!("Failed to insert tile: {e}"))?;
+    }
+
+    Ok(())
+}
+
+/// Turn decoded tile data into tile rows, row by row from `(origin_x, origin_y, row_width)`
+///
+/// Empty tiles (GID 0) are skipped and every placed tile grows `bounds`. `tile_id` is
+/// left at 0 for the caller to fill in.
+fn place_tiles(
+    layer_id: u32,
+    tiles: Vec<u32>,
+    (origin_x, origin_y, row_width): (i32, i32, u32),
+    bounds: &mut Option<TileBounds>,
+) -> Result<Vec<TiledTile>, String> {
+    if row_width == 0 {
+        return Err("Tile data has zero width".to_string());
+    }
+
+    let mut placed = Vec::new();
+    for (idx, gid_with_flags) in tiles.into_iter().enumerate() {
+        if gid_with_flags == 0 {
+            continue; // Skip empty tiles
+        }
+
+        let x = origin_x + (idx as u32 % row_width) as i32;
+        let y = origin_y + (idx as u32 / row_width) as i32;
+        let (gid, flip_h, flip_v, flip_d) = split_gid_flags(gid_with_flags);
+        extend_bounds(bounds, x, y);
+
+        placed.push(TiledTile {
+            tile_id: 0,
+            layer_id,
+            x,
+            y,
+            gid,
+            flip_h,
+            flip_v,
+            flip_d,
+        });
+    }
+
+    Ok(placed)
+}
+
+/// Bounding box of the tiles in a layer: `(min_x, min_y, max_x, max_y)`
+type TileBounds = (i32, i32, i32, i32);
+
+/// Grow `bounds` to include the tile at `x`, `y`
+fn extend_bounds(bounds: &mut Option<TileBounds>, x: i32, y: i32) {
+    *bounds = Some(match *bounds {
+        Some((min_x, min_y, max_x, max_y)) => {
+            (min_x.min(x), min_y.min(y), max_x.max(x), max_y.max(y))
+        }
+        None => (x, y, x, y),
+    });
+}
+
+/// Record the tile bounds on a layer row once its tiles have been stored
+fn set_layer_bounds(
+    ctx: &ReducerContext,
+    layer_id: u32,
+    bounds: Option<TileBounds>,
+) -> Result<(), String> {
+    let Some((min_x, min_y, max_x, max_y)) = bounds else {
+        return Ok(());
+    };
+
+    let layer = ctx
+        .db
+        .tiled_layer()
+        .layer_id()
+        .find(layer_id)
+        .ok_or_else(|| format!("Layer {layer_id} not found"))?;
+
+    ctx.db.tiled_layer().layer_id().update(TiledLayer {
+        min_x: Some(min_x),
+        min_y: Some(min_y),
+        max_x: Some(max_x),
+        max_y: Some(max_y),
+        ..layer
+    });
+
     Ok(())
 }
 
@@ -941,19 +2467,29 @@ fn store_object_layer(
     ctx: &ReducerContext,
     layer_id: u32,
     object_layer: tiled::ObjectLayer,
+    first_gids: &[u32],
 ) -> Result<(), String> {
     for object in object_layer.objects() {
         let object_id = generate_object_id(ctx)?;
+        let tile = object.tile_data();
+
+        let gid = tile.as_ref().and_then(|tile| {
+            let tileset_index = match tile.tileset_location() {
+                tiled::TilesetLocation::Map(tileset_index) => Some(*tileset_index),
+                // Template tiles use the template's tileset; find the map's copy of it
+                tiled::TilesetLocation::Template(template_tileset) => {
+                    let source = resolve_path("", &template_tileset.source.to_string_lossy());
+                    object_layer.map().tilesets().iter().position(|tileset| {
+                        resolve_path("", &tileset.source.to_string_lossy()) == source
+                    })
+                }
+            };
+            tileset_index
+                .and_then(|index| first_gids.get(index))
+                .map(|first_gid| first_gid + tile.id())
+        });
 
-        // Extract width and height from shape
-        let (width, height, shape_str) = match &object.shape {
-            tiled::ObjectShape::Rect { width, height } => (*width, *height, "rectangle"),
-            tiled::ObjectShape::Ellipse { width, height } => (*width, *height, "ellipse"),
-            tiled::ObjectShape::Point(..) => (0.0, 0.0, "point"),
-            tiled::ObjectShape::Polygon { .. } => (0.0, 0.0, "polygon"),
-            tiled::ObjectShape::Polyline { .. } => (0.0, 0.0, "polyline"),
-            tiled::ObjectShape::Text { width, height, .. } => (*width, *height, "text"),
-        };
+        let (width, height, shape_str) = shape_dimensions(&object.shape);
 
         ctx.db
             .tiled_object()
@@ -969,9 +2505,16 @@ fn store_object_layer(
                 rotation: object.rotation,
                 visible: object.visible,
                 shape: shape_str.to_string(),
+                points: shape_points(&object.shape),
+                gid,
+                flip_h: tile.as_ref().is_some_and(|tile| tile.flip_h),
+                flip_v: tile.as_ref().is_some_and(|tile| tile.flip_v),
+                flip_d: tile.as_ref().is_some_and(|tile| tile.flip_d),
             })
             .map_err(|e| format!("Failed to insert object: {e}"))?;
 
+        store_object_text(ctx, object_id, &object.shape)?;
+
         // Store object properties
         store_properties(ctx, "object", object_id, &object.properties)?;
     }
@@ -985,10 +2528,280 @@ fn store_object_layer(
     Ok(())
 }
 
-/// Store custom properties
-fn store_properties(
+/// Store the properties, tiles, animations, collision shapes and Wang sets of a tileset
+fn store_tileset_metadata(
     ctx: &ReducerContext,
-    parent_type: &str,
+    tileset_id: u32,
+    tileset: &tiled::Tileset,
+) -> Result<(), String> {
+    store_properties(ctx, "tileset", tileset_id as u64, &tileset.properties)?;
+    for (local_id, tile) in tileset.tiles() {
+        // The tiled crate pads image tilesets with default tiles (probability 0) that have
+        // no <tile> entry, so a <tile> whose only setting is probability="0" is lost here
+        if *tile == tiled::TileData::default() {
+            continue;
+        }
+
+        let tileset_tile_id = tileset_tile_key(tileset_id, local_id);
+        let row = TiledTilesetTile {
+            tileset_tile_id,
+            tileset_id,
+            local_id,
+            tile_type: tile.user_type.clone().unwrap_or_default(),
+            probability: tile.probability,
+            image_source: tile
+                .image
+                .as_ref()
+                .map(|img| img.source.to_string_lossy().to_string()),
+            image_width: tile.image.as_ref().map(|img| img.width as u32),
+            image_height: tile.image.as_ref().map(|img| img.height as u32),
+            image_rect_x: tile.image_rect.as_ref().map(|rect| rect.x as u32),
+            image_rect_y: tile.image_rect.as_ref().map(|rect| rect.y as u32),
+            image_rect_width: tile.image_rect.as_ref().map(|rect| rect.width as u32),
+            image_rect_height: tile.image_rect.as_ref().map(|rect| rect.height as u32),
+        };
+        let has_children = !tile.properties.is_empty()
+            || tile
+                .collision
+                .as_ref()
+                .is_some_and(|collision| !collision.object_data().is_empty())
+            || tile
+                .animation
+                .as_ref()
+                .is_some_and(|frames| !frames.is_empty());
+        if !has_tile_metadata(&row, has_children) {
+            continue;
+        }
+
+        ctx.db
+            .tiled_tileset_tile()
+            .try_insert(row)
+            .map_err(|e| format!("Failed to insert tileset tile: {e}"))?;
+
+        store_properties(ctx, "tileset_tile", tileset_tile_id, &tile.properties)?;
+
+        if let Some(collision) = &tile.collision {
+            store_tile_collision(ctx, tileset_tile_id, collision)?;
+        }
+
+        if let Some(animation) = &tile.animation {
+            for (frame_index, frame) in animation.iter().enumerate() {
+                insert_animation_frame(
+                    ctx,
+                    tileset_tile_id,
+                    frame_index as u32,
+                    frame.tile_id,
+                    frame.duration,
+                )?;
+            }
+        }
+    }
+
+    store_wang_sets(ctx, tileset_id, &tileset.wang_sets)?;
+
+    Ok(())
+}
+
+/// Store the Wang sets of a tileset with their colors and tiles
+fn store_wang_sets(
+    ctx: &ReducerContext,
+    tileset_id: u32,
+    wang_sets: &[tiled::WangSet],
+) -> Result<(), String> {
+    for wang_set in wang_sets {
+        let wang_set_id = generate_wang_set_id(ctx)?;
+
+        ctx.db
+            .tiled_wang_set()
+            .try_insert(TiledWangSet {
+                wang_set_id,
+                tileset_id,
+                name: wang_set.name.clone(),
+                wang_type: match wang_set.wang_set_type {
+                    tiled::WangSetType::Corner => "corner",
+                    tiled::WangSetType::Edge => "edge",
+                    tiled::WangSetType::Mixed => "mixed",
+                }
+                .to_string(),
+                tile_id: wang_set.tile,
+            })
+            .map_err(|e| format!("Failed to insert wang set: {e}"))?;
+
+        store_properties(ctx, "wang_set", wang_set_id as u64, &wang_set.properties)?;
+
+        for (color_index, wang_color) in wang_set.wang_colors.iter().enumerate() {
+            let wang_color_id = generate_wang_color_id(ctx)?;
+
+            ctx.db
+                .tiled_wang_color()
+                .try_insert(TiledWangColor {
+                    wang_color_id,
+                    wang_set_id,
+                    color_index: color_index as u32 + 1,
+                    name: wang_color.name.clone(),
+                    color: format_color(&wang_color.color),
+                    tile_id: wang_color.tile,
+                    probability: wang_color.probability,
+                })
+                .map_err(|e| format!("Failed to insert wang color: {e}"))?;
+
+            store_properties(
+                ctx,
+                "wang_color",
+                wang_color_id as u64,
+                &wang_color.properties,
+            )?;
+        }
+
+        // Sort for a stable insertion order; the tiled crate keeps these in a HashMap
+        let mut wang_tiles: Vec<_> = wang_set.wang_tiles.iter().collect();
+        wang_tiles.sort_by_key(|(local_id, _)| **local_id);
+        for (local_id, wang_tile) in wang_tiles {
+            insert_wang_tile(ctx, wang_set_id, *local_id, wang_tile.wang_id.0.to_vec())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Insert the Wang color assignment of one tile
+fn insert_wang_tile(
+    ctx: &ReducerContext,
+    wang_set_id: u32,
+    local_id: u32,
+    wang_id: Vec<u8>,
+) -> Result<(), String> {
+    let wang_tile_id = generate_wang_tile_id(ctx)?;
+
+    ctx.db
+        .tiled_wang_tile()
+        .try_insert(TiledWangTile {
+            wang_tile_id,
+            wang_set_id,
+            local_id,
+            wang_id,
+        })
+        .map_err(|e| format!("Failed to insert wang tile: {e}"))?;
+
+    Ok(())
+}
+
+/// Extract width, height and shape name from a tiled crate object shape
+fn shape_dimensions(shape: &tiled::ObjectShape) -> (f32, f32, &'static str) {
+    match shape {
+        tiled::ObjectShape::Rect { width, height } => (*width, *height, "rectangle"),
+        tiled::ObjectShape::Ellipse { width, height } => (*width, *height, "ellipse"),
+        tiled::ObjectShape::Point(..) => (0.0, 0.0, "point"),
+        tiled::ObjectShape::Polygon { .. } => (0.0, 0.0, "polygon"),
+        tiled::ObjectShape::Polyline { .. } => (0.0, 0.0, "polyline"),
+        tiled::ObjectShape::Text { width, height, .. } => (*width, *height, "text"),
+    }
+}
+
+/// Store the text payload of a tiled crate text object (other shapes are ignored)
+fn store_object_text(
+    ctx: &ReducerContext,
+    object_id: u64,
+    shape: &tiled::ObjectShape,
+) -> Result<(), String> {
+    if let tiled::ObjectShape::Text {
+        font_family,
+        pixel_size,
+        wrap,
+        color,
+        bold,
+        italic,
+        underline,
+        strikeout,
+        kerning,
+        halign,
+        valign,
+        text,
+        ..
+    } = shape
+    {
+        ctx.db
+            .tiled_object_text()
+            .try_insert(TiledObjectText {
+                object_id,
+                text: text.clone(),
+                font_family: font_family.clone(),
+                pixel_size: *pixel_size as u32,
+                wrap: *wrap,
+                color: format_color(color),
+                bold: *bold,
+                italic: *italic,
+                underline: *underline,
+                strikeout: *strikeout,
+                kerning: *kerning,
+                halign: match halign {
+                    tiled::HorizontalAlignment::Left => "left",
+                    tiled::HorizontalAlignment::Center => "center",
+                    tiled::HorizontalAlignment::Right => "right",
+                    tiled::HorizontalAlignment::Justify => "justify",
+                }
+                .to_string(),
+                valign: match valign {
+                    tiled::VerticalAlignment::Top => "top",
+                    tiled::VerticalAlignment::Center => "center",
+                    tiled::VerticalAlignment::Bottom => "bottom",
+                }
+                .to_string(),
+            })
+            .map_err(|e| format!("Failed to insert object text: {e}"))?;
+    }
+
+    Ok(())
+}
+
+/// Extract the vertices of a tiled crate polygon or polyline shape
+fn shape_points(shape: &tiled::ObjectShape) -> Vec<TiledPoint> {
+    match shape {
+        tiled::ObjectShape::Polygon { points } | tiled::ObjectShape::Polyline { points } => {
+            points.iter().map(|&(x, y)| TiledPoint { x, y }).collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/// Store the collision shapes of a tileset tile
+fn store_tile_collision(
+    ctx: &ReducerContext,
+    tileset_tile_id: u64,
+    collision: &tiled::ObjectLayerData,
+) -> Result<(), String> {
+    for object in collision.object_data() {
+        let collision_id = generate_tile_collision_id(ctx)?;
+        let (width, height, shape_str) = shape_dimensions(&object.shape);
+
+        ctx.db
+            .tiled_tile_collision()
+            .try_insert(TiledTileCollision {
+                collision_id,
+                tileset_tile_id,
+                name: object.name.clone(),
+                obj_type: object.user_type.clone(),
+                x: object.x,
+                y: object.y,
+                width,
+                height,
+                rotation: object.rotation,
+                visible: object.visible,
+                shape: shape_str.to_string(),
+                points: shape_points(&object.shape),
+            })
+            .map_err(|e| format!("Failed to insert tile collision: {e}"))?;
+
+        store_properties(ctx, "tile_collision", collision_id, &object.properties)?;
+    }
+
+    Ok(())
+}
+
+/// Store custom properties
+fn store_properties(
+    ctx: &ReducerContext,
+    parent_type: &str,
     parent_id: u64,
     properties: &tiled::Properties,
 ) -> Result<(), String> {
@@ -999,18 +2812,16 @@ fn store_properties(
             tiled::PropertyValue::BoolValue(v) => (v.to_string(), "bool"),
             tiled::PropertyValue::FloatValue(v) => (v.to_string(), "float"),
             tiled::PropertyValue::IntValue(v) => (v.to_string(), "int"),
-            tiled::PropertyValue::ColorValue(c) => {
-                // Format Color as hex string
-                (
-                    format!("#{:02x}{:02x}{:02x}{:02x}", c.red, c.green, c.blue, c.alpha),
-                    "color",
-                )
-            }
+            tiled::PropertyValue::ColorValue(c) => (format_color(c), "color"),
             tiled::PropertyValue::StringValue(v) => (v.clone(), "string"),
             tiled::PropertyValue::FileValue(v) => (v.clone(), "file"),
             tiled::PropertyValue::ObjectValue(v) => (v.to_string(), "object"),
             tiled::PropertyValue::ClassValue { .. } => ("".to_string(), "class"),
         };
+        let property_type = match value {
+            tiled::PropertyValue::ClassValue { property_type, .. } => property_type.clone(),
+            _ => String::new(),
+        };
 
         ctx.db
             .tiled_property()
@@ -1021,41 +2832,4273 @@ fn store_properties(
                 key: key.clone(),
                 value: value_str,
                 value_type: value_type.to_string(),
+                property_type,
             })
             .map_err(|e| format!("Failed to insert property: {e}"))?;
+
+        // Class members hang off the class property
+        if let tiled::PropertyValue::ClassValue { properties, .. } = value {
+            store_properties(ctx, "property", property_id, properties)?;
+        }
     }
 
     Ok(())
 }
 
-// ============================================================================
-// ID Generation Helpers
-// ============================================================================
+/// Insert a property parsed by the string loader, assigning it a fresh ID
+fn insert_property(ctx: &ReducerContext, property: TiledProperty) -> Result<u64, String> {
+    let property_id = generate_property_id(ctx)?;
 
-fn generate_map_id(ctx: &ReducerContext) -> Result<u32, String> {
-    Ok(ctx.db.tiled_map().count() as u32)
+    ctx.db
+        .tiled_property()
+        .try_insert(TiledProperty {
+            property_id,
+            ..property
+        })
+        .map_err(|e| format!("Failed to insert property: {e}"))?;
+
+    Ok(property_id)
 }
 
-fn generate_layer_id(ctx: &ReducerContext) -> Result<u32, String> {
-    Ok(ctx.db.tiled_layer().count() as u32)
+/// Insert one frame of a tile animation
+fn insert_animation_frame(
+    ctx: &ReducerContext,
+    tileset_tile_id: u64,
+    frame_index: u32,
+    frame_tile_id: u32,
+    duration_ms: u32,
+) -> Result<(), String> {
+    let frame_id = generate_tile_animation_id(ctx)?;
+
+    ctx.db
+        .tiled_tile_animation()
+        .try_insert(TiledTileAnimation {
+            frame_id,
+            ..animation_frame(tileset_tile_id, frame_index, frame_tile_id, duration_ms)
+        })
+        .map_err(|e| format!("Failed to insert animation frame: {e}"))?;
+
+    Ok(())
 }
 
-fn generate_tileset_id(ctx: &ReducerContext) -> Result<u32, String> {
-    Ok(ctx.db.tiled_tileset().count() as u32)
+/// Build one frame of a tile animation, with `frame_id` left at 0 for the caller to fill in
+fn animation_frame(
+    tileset_tile_id: u64,
+    frame_index: u32,
+    frame_tile_id: u32,
+    duration_ms: u32,
+) -> TiledTileAnimation {
+    TiledTileAnimation {
+        frame_id: 0,
+        tileset_tile_id,
+        tileset_id: (tileset_tile_id >> 32) as u32,
+        local_id: tileset_tile_id as u32,
+        frame_index,
+        frame_tile_id,
+        duration_ms,
+    }
 }
 
-fn generate_tile_id(ctx: &ReducerContext) -> Result<u64, String> {
-    Ok(ctx.db.tiled_tile().count())
+/// Parse the `wangid` of a `<wangtile>` into its 8 color indexes
+///
+/// Tiled 1.5+ writes a comma-separated list. Older versions wrote a hex number with one
+/// nibble per index, starting from the top edge in the lowest nibble, where edges and
+/// corners counted their colors separately; `legacy_colors` holds the set's indexes of the
+/// `<wangedgecolor>`s and `<wangcornercolor>`s those counts refer to.
+fn parse_wang_id(value: &str, legacy_colors: &(Vec<u8>, Vec<u8>)) -> Result<Vec<u8>, String> {
+    let invalid = || format!("Invalid wang ID: {value}");
+
+    let Some(hex) = value.strip_prefix("0x") else {
+        let wang_id = value
+            .split(',')
+            .map(|index| index.trim().parse::<u8>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+        return if wang_id.len() == 8 {
+            Ok(wang_id)
+        } else {
+            Err(invalid())
+        };
+    };
+
+    let bits = u32::from_str_radix(hex, 16).map_err(|_| invalid())?;
+    (0..8)
+        .map(|index| {
+            let color = ((bits >> (index * 4)) & 0xf) as usize;
+            // Even indexes are edges, odd ones corners
+            let colors = if index % 2 == 0 {
+                &legacy_colors.0
+            } else {
+                &legacy_colors.1
+            };
+            match color {
+                0 => Ok(0),
+                _ => colors.get(color - 1).copied().ok_or_else(invalid),
+            }
+        })
+        .collect()
 }
 
-fn generate_object_id(ctx: &ReducerContext) -> Result<u64, String> {
-    Ok(ctx.db.tiled_object().count())
+/// Primary key of a `TiledTilesetTile`: the tileset ID in the high 32 bits and
+/// the local tile ID in the low 32 bits
+fn tileset_tile_key(tileset_id: u32, local_id: u32) -> u64 {
+    ((tileset_id as u64) << 32) | local_id as u64
 }
 
-fn generate_property_id(ctx: &ReducerContext) -> Result<u64, String> {
-    Ok(ctx.db.tiled_property().count())
+/// Whether a tileset tile says more than an empty `<tile>` entry: a class, a probability
+/// other than 1, an image, properties, collision shapes or animation frames (`has_children`).
+/// Every loader stores a `TiledTilesetTile` only for such tiles.
+fn has_tile_metadata(tile: &TiledTilesetTile, has_children: bool) -> bool {
+    has_children
+        || !tile.tile_type.is_empty()
+        || tile.probability != 1.0
+        || tile.image_source.is_some()
+        || tile.image_rect_x.is_some()
+        || tile.image_rect_y.is_some()
+        || tile.image_rect_width.is_some()
+        || tile.image_rect_height.is_some()
 }
 
-// Note: This library only provides table definitions and the load_tmx_map() function.
-// You should define your own reducers in your SpacetimeDB module that use these tables.
-// See examples/simple_game/server/src/lib.rs for examples of reducers you can create.
+/// Format a tiled crate color as `#rrggbbaa`, the form both loaders store
+fn format_color(c: &tiled::Color) -> String {
+    format!("#{:02x}{:02x}{:02x}{:02x}", c.red, c.green, c.blue, c.alpha)
+}
+
+/// Convert a TMX color (`#AARRGGBB` or `#RRGGBB`) to the `#rrggbbaa` form the
+/// `tiled` crate loader stores
+///
+/// Anything else, including values that aren't hex digits, is returned unchanged.
+fn normalize_color(value: &str) -> String {
+    let hex = value.trim_start_matches('#');
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return value.to_string();
+    }
+
+    match hex.len() {
+        8 => format!("#{}{}", &hex[2..], &hex[..2]).to_lowercase(),
+        6 => format!("#{hex}ff").to_lowercase(),
+        _ => value.to_string(),
+    }
+}
+
+/// Create a reader for TMX, TSX or TX content, configured the way the string loader expects
+fn xml_reader(content: &str) -> quick_xml::Reader<&[u8]> {
+    let mut reader = quick_xml::Reader::from_str(content);
+    reader.config_mut().trim_text(true);
+    // Report `<foo/>` as a Start/End pair so every element closes the same way
+    reader.config_mut().expand_empty_elements = true;
+    reader
+}
+
+/// Join a path onto a directory and normalize `.` and `..` segments
+///
+/// This builds the keys external files are looked up and stored under. Both use `/`
+/// separators; `\` is accepted too. The result has no leading `./`, and `..` segments
+/// that go above a relative base are kept. An absolute `path` replaces `base_dir`, and
+/// an absolute result keeps its leading `/`.
+fn resolve_path(base_dir: &str, path: &str) -> String {
+    let base_dir = if path.starts_with(['/', '\\']) {
+        ""
+    } else {
+        base_dir
+    };
+    let root = if base_dir.starts_with(['/', '\\']) || path.starts_with(['/', '\\']) {
+        "/"
+    } else {
+        ""
+    };
+    let mut parts: Vec<&str> = Vec::new();
+
+    for part in base_dir.split(['/', '\\']).chain(path.split(['/', '\\'])) {
+        match part {
+            "" | "." => {}
+            ".." => match parts.last() {
+                Some(last) if *last != ".." => {
+                    parts.pop();
+                }
+                // Nothing is above the root
+                None if !root.is_empty() => {}
+                _ => parts.push(part),
+            },
+            part => parts.push(part),
+        }
+    }
+
+    format!("{root}{}", parts.join("/"))
+}
+
+/// Get the directory part of a `/`-separated path, or `""` for a bare file name
+fn parent_dir(path: &str) -> &str {
+    match path.rfind('/') {
+        Some(0) => "/",
+        Some(end) => &path[..end],
+        None => "",
+    }
+}
+
+/// Find the content of a file in the sources passed to [`load_tmx_map_with_sources`]
+fn find_source<'a>(sources: &'a HashMap<String, String>, path: &str) -> Option<&'a str> {
+    sources
+        .iter()
+        .find(|(key, _)| resolve_path("", key) == path)
+        .map(|(_, content)| content.as_str())
+}
+
+/// Find a stored external tileset that holds the metadata of the tileset file at `source`
+/// with content hash `source_hash`
+fn find_external_tileset(
+    ctx: &ReducerContext,
+    source: &str,
+    source_hash: u64,
+) -> Option<TiledTileset> {
+    ctx.db
+        .tiled_tileset()
+        .iter()
+        .find(|tileset| is_external_definition(tileset, source, source_hash))
+}
+
+/// Whether `tileset` holds the metadata of the external tileset file at `source` with
+/// content hash `source_hash`
+fn is_external_definition(tileset: &TiledTileset, source: &str, source_hash: u64) -> bool {
+    // Tilesets stored without their content have no hash
+    tileset.source.as_deref() == Some(source)
+        && tileset.source_hash == Some(source_hash)
+        && tileset.definition_tileset_id == tileset.tileset_id
+}
+
+/// Hash a tileset file's content to tell versions of it apart (64-bit FNV-1a, which
+/// unlike `DefaultHasher` stays the same across builds)
+fn content_hash(content: &str) -> u64 {
+    content.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Get the tileset holding the metadata of a tileset (see `TiledTileset::definition_tileset_id`)
+fn definition_tileset_id(ctx: &ReducerContext, tileset_id: u32) -> u32 {
+    ctx.db
+        .tiled_tileset()
+        .tileset_id()
+        .find(tileset_id)
+        .map_or(tileset_id, |tileset| tileset.definition_tileset_id)
+}
+
+/// Parse the attributes of an `<object>` element
+///
+/// `object_id` and `layer_id` are left at 0 for the caller to fill in.
+fn parse_object(e: &quick_xml::events::BytesStart) -> Result<TiledObject, String> {
+    let mut name = String::new();
+    let mut obj_type = String::new();
+    let mut x = 0.0f32;
+    let mut y = 0.0f32;
+    let mut width = 0.0f32;
+    let mut height = 0.0f32;
+    let mut rotation = 0.0f32;
+    let mut visible = true;
+    let mut raw_gid = 0u32;
+
+    for attr in e.attributes() {
+        let attr = attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+        match attr.key.as_ref() {
+            b"name" => {
+                name = attr
+                    .unescape_value()
+                    .map_err(|e| format!("Failed to parse attribute: {e}"))?
+                    .to_string()
+            }
+            // Tiled 1.9 renamed the object's `type` to `class`
+            b"type" | b"class" => {
+                obj_type = attr
+                    .unescape_value()
+                    .map_err(|e| format!("Failed to parse attribute: {e}"))?
+                    .to_string()
+            }
+            b"x" => {
+                x = std::str::from_utf8(&attr.value)
+                    .unwrap()
+                    .parse()
+                    .unwrap_or(0.0)
+            }
+            b"y" => {
+                y = std::str::from_utf8(&attr.value)
+                    .unwrap()
+                    .parse()
+                    .unwrap_or(0.0)
+            }
+            b"width" => {
+                width = std::str::from_utf8(&attr.value)
+                    .unwrap()
+                    .parse()
+                    .unwrap_or(0.0)
+            }
+            b"height" => {
+                height = std::str::from_utf8(&attr.value)
+                    .unwrap()
+                    .parse()
+                    .unwrap_or(0.0)
+            }
+            b"rotation" => {
+                rotation = std::str::from_utf8(&attr.value)
+                    .unwrap()
+                    .parse()
+                    .unwrap_or(0.0)
+            }
+            b"visible" => visible = std::str::from_utf8(&attr.value).unwrap() == "1",
+            b"gid" => {
+                raw_gid = std::str::from_utf8(&attr.value)
+                    .unwrap()
+                    .parse()
+                    .unwrap_or(0)
+            }
+            _ => {}
+        }
+    }
+
+    let (gid, flip_h, flip_v, flip_d) = split_gid_flags(raw_gid);
+
+    // Objects without a shape child element are rectangles, even with no size
+    Ok(TiledObject {
+        object_id: 0,
+        layer_id: 0,
+        name,
+        obj_type,
+        x,
+        y,
+        width,
+        height,
+        rotation,
+        visible,
+        shape: "rectangle".to_string(),
+        points: Vec::new(),
+        gid: (gid != 0).then_some(gid),
+        flip_h,
+        flip_v,
+        flip_d,
+    })
+}
+
+/// Parse the attributes of a `<tileset>` element
+///
+/// `tileset_id`, `map_id`, `tileset_index` and `definition_tileset_id` are left at 0
+/// for the caller to fill in. `source` is the normalized path of an external tileset
+/// reference. The grid defaults to the tile size until a `<grid>` child says otherwise.
+fn parse_tileset(e: &quick_xml::events::BytesStart) -> Result<TiledTileset, String> {
+    let mut tileset = TiledTileset {
+        tileset_id: 0,
+        map_id: 0,
+        tileset_index: 0,
+        first_gid: 0,
+        name: String::new(),
+        tile_width: 0,
+        tile_height: 0,
+        tile_count: 0,
+        columns: 0,
+        spacing: 0,
+        margin: 0,
+        tile_offset_x: 0,
+        tile_offset_y: 0,
+        object_alignment: String::from("unspecified"),
+        tile_render_size: String::from("tile"),
+        fill_mode: String::from("stretch"),
+        grid_orientation: String::from("orthogonal"),
+        grid_width: 0,
+        grid_height: 0,
+        allow_flip_h: false,
+        allow_flip_v: false,
+        allow_rotate: false,
+        prefer_untransformed: false,
+        image_source: None,
+        image_width: None,
+        image_height: None,
+        source: None,
+        source_hash: None,
+        definition_tileset_id: 0,
+    };
+
+    for attr in e.attributes() {
+        let attr = attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+        let value = attr
+            .unescape_value()
+            .map_err(|e| format!("Failed to parse attribute: {e}"))?;
+        match attr.key.as_ref() {
+            b"source" => tileset.source = Some(resolve_path("", &value)),
+            b"firstgid" => tileset.first_gid = value.parse().unwrap_or(0),
+            b"name" => tileset.name = value.to_string(),
+            b"tilewidth" => tileset.tile_width = value.parse().unwrap_or(0),
+            b"tileheight" => tileset.tile_height = value.parse().unwrap_or(0),
+            b"tilecount" => tileset.tile_count = value.parse().unwrap_or(0),
+            b"columns" => tileset.columns = value.parse().unwrap_or(0),
+            b"spacing" => tileset.spacing = value.parse().unwrap_or(0),
+            b"margin" => tileset.margin = value.parse().unwrap_or(0),
+            b"objectalignment" => tileset.object_alignment = value.to_string(),
+            b"tilerendersize" => tileset.tile_render_size = value.to_string(),
+            b"fillmode" => tileset.fill_mode = value.to_string(),
+            _ => {}
+        }
+    }
+
+    tileset.grid_width = tileset.tile_width;
+    tileset.grid_height = tileset.tile_height;
+
+    Ok(tileset)
+}
+
+/// Apply a `<tileoffset>`, `<grid>` or `<transformations>` child element to its tileset
+fn parse_tileset_child(
+    e: &quick_xml::events::BytesStart,
+    tileset: &mut TiledTileset,
+) -> Result<(), String> {
+    for attr in e.attributes() {
+        let attr = attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+        let value = attr
+            .unescape_value()
+            .map_err(|e| format!("Failed to parse attribute: {e}"))?;
+        match (e.name().as_ref(), attr.key.as_ref()) {
+            (b"tileoffset", b"x") => tileset.tile_offset_x = value.parse().unwrap_or(0),
+            (b"tileoffset", b"y") => tileset.tile_offset_y = value.parse().unwrap_or(0),
+            (b"grid", b"orientation") => tileset.grid_orientation = value.to_string(),
+            (b"grid", b"width") => tileset.grid_width = value.parse().unwrap_or(0),
+            (b"grid", b"height") => tileset.grid_height = value.parse().unwrap_or(0),
+            (b"transformations", b"hflip") => tileset.allow_flip_h = value == "1",
+            (b"transformations", b"vflip") => tileset.allow_flip_v = value == "1",
+            (b"transformations", b"rotate") => tileset.allow_rotate = value == "1",
+            (b"transformations", b"preferuntransformed") => {
+                tileset.prefer_untransformed = value == "1"
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse the attributes of a `<map>` element
+///
+/// `map_id` is left at 0 and `name` empty for the caller to fill in.
+fn parse_map(e: &quick_xml::events::BytesStart) -> Result<TiledMap, String> {
+    let mut map = TiledMap {
+        map_id: 0,
+        name: String::new(),
+        width: 0,
+        height: 0,
+        tile_width: 0,
+        tile_height: 0,
+        orientation: String::from("orthogonal"),
+        background_color: None,
+        infinite: false,
+        render_order: String::from("right-down"),
+        stagger_axis: None,
+        stagger_index: None,
+        hex_side_length: None,
+        parallax_origin_x: 0.0,
+        parallax_origin_y: 0.0,
+        class: String::new(),
+        version: String::new(),
+        tiled_version: String::new(),
+        next_layer_id: 0,
+        next_object_id: 0,
+    };
+
+    for attr in e.attributes() {
+        let attr = attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+        let value = attr
+            .unescape_value()
+            .map_err(|e| format!("Failed to parse attribute: {e}"))?;
+        match attr.key.as_ref() {
+            b"width" => map.width = value.parse().unwrap_or(0),
+            b"height" => map.height = value.parse().unwrap_or(0),
+            b"tilewidth" => map.tile_width = value.parse().unwrap_or(0),
+            b"tileheight" => map.tile_height = value.parse().unwrap_or(0),
+            b"orientation" => map.orientation = value.to_string(),
+            b"backgroundcolor" => map.background_color = Some(normalize_color(&value)),
+            b"infinite" => map.infinite = value == "1",
+            b"renderorder" => map.render_order = value.to_string(),
+            b"staggeraxis" => map.stagger_axis = Some(value.to_string()),
+            b"staggerindex" => map.stagger_index = Some(value.to_string()),
+            b"hexsidelength" => map.hex_side_length = value.parse().ok(),
+            b"parallaxoriginx" => map.parallax_origin_x = value.parse().unwrap_or(0.0),
+            b"parallaxoriginy" => map.parallax_origin_y = value.parse().unwrap_or(0.0),
+            b"class" => map.class = value.to_string(),
+            b"version" => map.version = value.to_string(),
+            b"tiledversion" => map.tiled_version = value.to_string(),
+            b"nextlayerid" => map.next_layer_id = value.parse().unwrap_or(0),
+            b"nextobjectid" => map.next_object_id = value.parse().unwrap_or(0),
+            _ => {}
+        }
+    }
+
+    Ok(map)
+}
+
+/// Parse the attributes of a `<layer>`, `<objectgroup>`, `<imagelayer>` or `<group>` element
+///
+/// `layer_id`, `map_id` and `z_order` are left at 0 and `parent_layer_id` empty for the
+/// caller to fill in.
+fn parse_layer(e: &quick_xml::events::BytesStart, layer_type: &str) -> Result<TiledLayer, String> {
+    let mut layer = TiledLayer {
+        layer_id: 0,
+        map_id: 0,
+        name: String::new(),
+        layer_type: layer_type.to_string(),
+        visible: true,
+        opacity: 1.0,
+        offset_x: 0,
+        offset_y: 0,
+        z_order: 0,
+        parent_layer_id: None,
+        tint_color: None,
+        parallax_x: 1.0,
+        parallax_y: 1.0,
+        class: String::new(),
+        min_x: None,
+        min_y: None,
+        max_x: None,
+        max_y: None,
+    };
+
+    for attr in e.attributes() {
+        let attr = attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+        let value = attr
+            .unescape_value()
+            .map_err(|e| format!("Failed to parse attribute: {e}"))?;
+        match attr.key.as_ref() {
+            b"name" => layer.name = value.to_string(),
+            b"visible" => layer.visible = value == "1",
+            b"opacity" => layer.opacity = value.parse().unwrap_or(1.0),
+            b"offsetx" => layer.offset_x = value.parse().unwrap_or(0),
+            b"offsety" => layer.offset_y = value.parse().unwrap_or(0),
+            b"tintcolor" => layer.tint_color = Some(normalize_color(&value)),
+            b"parallaxx" => layer.parallax_x = value.parse().unwrap_or(1.0),
+            b"parallaxy" => layer.parallax_y = value.parse().unwrap_or(1.0),
+            b"class" => layer.class = value.to_string(),
+            _ => {}
+        }
+    }
+
+    Ok(layer)
+}
+
+/// Parse the attributes of a `<property>` element
+///
+/// `parent_type` and `parent_id` are left empty for the caller to fill in.
+fn parse_property(e: &quick_xml::events::BytesStart) -> Result<TiledProperty, String> {
+    let mut key = String::new();
+    let mut value = String::new();
+    let mut value_type = String::from("string");
+    let mut property_type = String::new();
+
+    for attr in e.attributes() {
+        let attr = attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+        match attr.key.as_ref() {
+            b"name" => {
+                key = attr
+                    .unescape_value()
+                    .map_err(|e| format!("Failed to parse property: {e}"))?
+                    .to_string()
+            }
+            b"value" => {
+                value = attr
+                    .unescape_value()
+                    .map_err(|e| format!("Failed to parse property: {e}"))?
+                    .to_string()
+            }
+            b"type" => {
+                value_type = attr
+                    .unescape_value()
+                    .map_err(|e| format!("Failed to parse property: {e}"))?
+                    .to_string()
+            }
+            b"propertytype" => {
+                property_type = attr
+                    .unescape_value()
+                    .map_err(|e| format!("Failed to parse property: {e}"))?
+                    .to_string()
+            }
+            _ => {}
+        }
+    }
+
+    if value_type == "color" {
+        value = normalize_color(&value);
+    }
+
+    Ok(TiledProperty {
+        property_id: 0,
+        parent_type: String::new(),
+        parent_id: 0,
+        key,
+        value,
+        value_type,
+        property_type,
+    })
+}
+
+/// Parse the formatting attributes of a `<text>` element
+///
+/// `object_id` is left at 0 and `text` empty for the caller to fill in.
+fn parse_text(e: &quick_xml::events::BytesStart) -> Result<TiledObjectText, String> {
+    let mut text = TiledObjectText {
+        object_id: 0,
+        text: String::new(),
+        font_family: "sans-serif".to_string(),
+        pixel_size: 16,
+        wrap: false,
+        color: "#000000ff".to_string(),
+        bold: false,
+        italic: false,
+        underline: false,
+        strikeout: false,
+        kerning: true,
+        halign: "left".to_string(),
+        valign: "top".to_string(),
+    };
+
+    for attr in e.attributes() {
+        let attr = attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+        let value = attr
+            .unescape_value()
+            .map_err(|e| format!("Failed to parse attribute: {e}"))?;
+        match attr.key.as_ref() {
+            b"fontfamily" => text.font_family = value.to_string(),
+            b"pixelsize" => text.pixel_size = value.parse().unwrap_or(16),
+            b"wrap" => text.wrap = value == "1",
+            b"color" => text.color = normalize_color(&value),
+            b"bold" => text.bold = value == "1",
+            b"italic" => text.italic = value == "1",
+            b"underline" => text.underline = value == "1",
+            b"strikeout" => text.strikeout = value == "1",
+            b"kerning" => text.kerning = value == "1",
+            b"halign" => text.halign = value.to_string(),
+            b"valign" => text.valign = value.to_string(),
+            _ => {}
+        }
+    }
+
+    Ok(text)
+}
+
+/// Parse a TMX `points` attribute ("x1,y1 x2,y2 ...")
+fn parse_points(value: &str) -> Vec<TiledPoint> {
+    value
+        .split_whitespace()
+        .filter_map(|pair| {
+            let (x, y) = pair.split_once(',')?;
+            Some(TiledPoint {
+                x: x.parse().ok()?,
+                y: y.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Set the shape of the object or tile collision shape a shape child element belongs to
+///
+/// Points have no size, matching the tiled crate's `ObjectShape::Point`.
+fn set_object_shape(
+    rows: &mut MapRows,
+    owner: Option<(&'static str, u64)>,
+    shape: &str,
+    points: Vec<TiledPoint>,
+) {
+    let is_point = shape == "point";
+
+    match owner {
+        Some(("object", object_id)) => {
+            if let Some(object) = rows
+                .objects
+                .iter_mut()
+                .find(|object| object.object_id == object_id)
+            {
+                if is_point {
+                    object.width = 0.0;
+                    object.height = 0.0;
+                }
+                object.shape = shape.to_string();
+                object.points = points;
+            }
+        }
+        Some(("tile_collision", collision_id)) => {
+            if let Some(collision) = rows
+                .collisions
+                .iter_mut()
+                .find(|collision| collision.collision_id == collision_id)
+            {
+                if is_point {
+                    collision.width = 0.0;
+                    collision.height = 0.0;
+                }
+                collision.shape = shape.to_string();
+                collision.points = points;
+            }
+        }
+        _ => {}
+    }
+}
+
+/// An object template (`.tx` file) read by the string loader
+struct ObjectTemplate {
+    /// Raw attributes of the template's `<object>`, with `gid` converted to a GID of the map
+    attributes: Vec<(Vec<u8>, Vec<u8>)>,
+    /// Shape set by a child element of the template object, and its vertices
+    shape: Option<(String, Vec<TiledPoint>)>,
+    /// Text payload of template text objects (`object_id` is 0)
+    text: Option<TiledObjectText>,
+    /// Template properties. Class members have `parent_type` "property" and the index of
+    /// their class property in `parent_id`; the others have an empty `parent_type`.
+    properties: Vec<TiledProperty>,
+}
+
+/// Parse the `.tx` file at `path`
+///
+/// `map_first_gid` gives the first GID of the map's tileset with a source path, so tile
+/// templates get a GID that's valid in the map.
+fn parse_template(
+    path: &str,
+    content: &str,
+    map_first_gid: impl Fn(&str) -> Option<u32>,
+) -> Result<ObjectTemplate, String> {
+    use quick_xml::events::Event;
+
+    let mut reader = xml_reader(content);
+    let template_dir = parent_dir(path);
+
+    let mut template = ObjectTemplate {
+        attributes: Vec::new(),
+        shape: None,
+        text: None,
+        properties: Vec::new(),
+    };
+    // First GID of the template's tileset, in the template and in the map
+    let mut tileset_gids: Option<(u32, u32)> = None;
+    let mut pending_property: Option<TiledProperty> = None;
+    // Indices of the open class properties in `template.properties`
+    let mut class_stack: Vec<usize> = Vec::new();
+    let mut in_text = false;
+    let mut depth = 0u32;
+    let mut buf = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                depth += 1;
+
+                match e.name().as_ref() {
+                    b"tileset" => {
+                        let mut first_gid = 1u32;
+                        let mut source = String::new();
+
+                        for attr in e.attributes() {
+                            let attr =
+                                attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+                            match attr.key.as_ref() {
+                                b"firstgid" => {
+                                    first_gid = std::str::from_utf8(&attr.value)
+                                        .unwrap()
+                                        .parse()
+                                        .unwrap_or(1)
+                                }
+                                b"source" => {
+                                    source = attr
+                                        .unescape_value()
+                                        .map_err(|e| format!("Failed to parse attribute: {e}"))?
+                                        .to_string()
+                                }
+                                _ => {}
+                            }
+                        }
+
+                        let source = resolve_path(template_dir, &source);
+                        match map_first_gid(&source) {
+                            Some(map_first_gid) => tileset_gids = Some((first_gid, map_first_gid)),
+                            None => log::warn!(
+                                "Template '{path}' uses tileset '{source}', which the map doesn't reference"
+                            ),
+                        }
+                    }
+                    // The template's object is the only one; tile collision objects are deeper
+                    b"object" if depth == 2 => {
+                        for attr in e.attributes() {
+                            let attr =
+                                attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+
+                            if attr.key.as_ref() == b"gid" {
+                                let raw_gid: u32 = std::str::from_utf8(&attr.value)
+                                    .unwrap()
+                                    .parse()
+                                    .unwrap_or(0);
+                                let gid = raw_gid & GID_MASK;
+
+                                match tileset_gids {
+                                    Some((first_gid, map_first_gid)) if gid >= first_gid => {
+                                        let map_gid = (raw_gid & !GID_MASK)
+                                            | (map_first_gid + gid - first_gid);
+                                        template.attributes.push((
+                                            b"gid".to_vec(),
+                                            map_gid.to_string().into_bytes(),
+                                        ));
+                                    }
+                                    _ => {}
+                                }
+                            } else {
+                                template
+                                    .attributes
+                                    .push((attr.key.as_ref().to_vec(), attr.value.to_vec()));
+                            }
+                        }
+                    }
+                    b"text" if depth == 3 => {
+                        template.shape = Some(("text".to_string(), Vec::new()));
+                        template.text = Some(parse_text(&e)?);
+                        in_text = true;
+                        reader.config_mut().trim_text(false);
+                    }
+                    b"ellipse" | b"point" if depth == 3 => {
+                        let shape = std::str::from_utf8(e.name().as_ref()).unwrap().to_string();
+                        template.shape = Some((shape, Vec::new()));
+                    }
+                    b"polygon" | b"polyline" if depth == 3 => {
+                        let shape = std::str::from_utf8(e.name().as_ref()).unwrap().to_string();
+                        let mut points = Vec::new();
+
+                        for attr in e.attributes() {
+                            let attr =
+                                attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+                            if attr.key.as_ref() == b"points" {
+                                points = parse_points(
+                                    &attr
+                                        .unescape_value()
+                                        .map_err(|e| format!("Failed to parse attribute: {e}"))?,
+                                );
+                            }
+                        }
+
+                        template.shape = Some((shape, points));
+                    }
+                    // <template><object><properties><property>, or a class member below it
+                    b"property" if depth >= 4 => {
+                        let mut property = parse_property(&e)?;
+                        if let Some(&class_index) = class_stack.last() {
+                            property.parent_type = "property".to_string();
+                            property.parent_id = class_index as u64;
+                        }
+
+                        if property.value_type == "class" {
+                            class_stack.push(template.properties.len());
+                            template.properties.push(property);
+                        } else {
+                            pending_property = Some(property);
+                            reader.config_mut().trim_text(false);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Event::Text(e)) if pending_property.is_some() => {
+                if let Some(property) = pending_property.as_mut() {
+                    let text = e
+                        .unescape()
+                        .map_err(|e| format!("Failed to read property value: {e}"))?;
+                    property.value.push_str(&text);
+                }
+            }
+            Ok(Event::Text(e)) if in_text => {
+                if let Some(text) = template.text.as_mut() {
+                    let content = e
+                        .unescape()
+                        .map_err(|e| format!("Failed to read object text: {e}"))?;
+                    text.text.push_str(&content);
+                }
+            }
+            Ok(Event::End(e)) => {
+                depth = depth.saturating_sub(1);
+
+                match e.name().as_ref() {
+                    b"property" => {
+                        match pending_property.take() {
+                            Some(property) => template.properties.push(property),
+                            None => {
+                                class_stack.pop();
+                            }
+                        }
+                        reader.config_mut().trim_text(true);
+                    }
+                    b"text" => {
+                        in_text = false;
+                        reader.config_mut().trim_text(true);
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(format!("Failed to parse template '{path}': {e}")),
+            _ => {}
+        }
+
+        buf.clear();
+    }
+
+    Ok(template)
+}
+
+/// Overlay the attributes of an `<object>` element on those of its template
+fn merge_template_attributes(
+    e: &quick_xml::events::BytesStart,
+    template: &ObjectTemplate,
+) -> Result<quick_xml::events::BytesStart<'static>, String> {
+    let mut merged = quick_xml::events::BytesStart::new("object");
+    let mut instance_keys = Vec::new();
+
+    for attr in e.attributes() {
+        let attr = attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+        instance_keys.push(attr.key.as_ref().to_vec());
+        merged.push_attribute(attr);
+    }
+
+    for (key, value) in &template.attributes {
+        if !instance_keys.contains(key) {
+            merged.push_attribute((key.as_slice(), value.as_slice()));
+        }
+    }
+
+    Ok(merged)
+}
+
+/// Where a template property an object instance doesn't override is stored
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TemplatePropertyParent {
+    /// The object itself
+    Object,
+    /// A class property the instance sets, by `property_id`
+    Instance(u64),
+    /// A template property stored before it, by its index in the returned list
+    Template(usize),
+}
+
+/// Pick the template properties an object instance doesn't override, in storing order
+///
+/// `instance` holds the properties stored for the instance, class members included. A
+/// class property set on both keeps the template's members the instance doesn't set, at
+/// any depth; any other property set on the instance replaces the template's.
+fn missing_template_properties<'a>(
+    template: &'a [TiledProperty],
+    instance: &[TiledProperty],
+) -> Vec<(TemplatePropertyParent, &'a TiledProperty)> {
+    let mut missing = Vec::new();
+    // Where each template property went, None if the instance overrides it
+    let mut placed: Vec<Option<TemplatePropertyParent>> = Vec::new();
+
+    for property in template {
+        // Members go where their class went
+        let parent = if property.parent_type == "property" {
+            placed[property.parent_id as usize]
+        } else {
+            Some(TemplatePropertyParent::Object)
+        };
+
+        let overridden = parent.and_then(|parent| {
+            instance.iter().find(|stored| {
+                stored.key == property.key
+                    && match parent {
+                        TemplatePropertyParent::Object => stored.parent_type != "property",
+                        TemplatePropertyParent::Instance(class_id) => {
+                            stored.parent_type == "property" && stored.parent_id == class_id
+                        }
+                        // A class stored from the template has no instance members
+                        TemplatePropertyParent::Template(_) => false,
+                    }
+            })
+        });
+
+        placed.push(match (parent, overridden) {
+            (None, _) => None,
+            (Some(_), Some(stored))
+                if stored.value_type == "class" && property.value_type == "class" =>
+            {
+                Some(TemplatePropertyParent::Instance(stored.property_id))
+            }
+            (Some(_), Some(_)) => None,
+            (Some(parent), None) => {
+                missing.push((parent, property));
+                Some(TemplatePropertyParent::Template(missing.len() - 1))
+            }
+        });
+    }
+
+    missing
+}
+
+/// Store the template properties and text an object instance doesn't override
+///
+/// Called once the instance's own child elements have been read.
+fn apply_template_children(
+    rows: &mut MapRows,
+    parent_type: &str,
+    parent_id: u64,
+    template: &ObjectTemplate,
+) {
+    let properties_of = |parent_type: &str, parent_id: u64| -> Vec<TiledProperty> {
+        rows.properties
+            .iter()
+            .filter(|p| p.parent_type == parent_type && p.parent_id == parent_id)
+            .cloned()
+            .collect()
+    };
+
+    // The instance's properties and, level by level, the members of its class properties
+    let mut instance = properties_of(parent_type, parent_id);
+    let mut next = 0;
+    while let Some(property) = instance.get(next) {
+        if property.value_type == "class" {
+            let members = properties_of("property", property.property_id);
+            instance.extend(members);
+        }
+        next += 1;
+    }
+
+    let mut property_ids = Vec::new();
+    for (parent, property) in missing_template_properties(&template.properties, &instance) {
+        let (parent_type, parent_id) = match parent {
+            TemplatePropertyParent::Object => (parent_type, parent_id),
+            TemplatePropertyParent::Instance(class_id) => ("property", class_id),
+            TemplatePropertyParent::Template(index) => ("property", property_ids[index]),
+        };
+        property_ids.push(rows.add_property(TiledProperty {
+            parent_type: parent_type.to_string(),
+            parent_id,
+            ..property.clone()
+        }));
+    }
+
+    if let Some(text) = &template.text {
+        if parent_type == "object"
+            && !rows
+                .object_texts
+                .iter()
+                .any(|text| text.object_id == parent_id)
+        {
+            rows.object_texts.push(TiledObjectText {
+                object_id: parent_id,
+                ..text.clone()
+            });
+        }
+    }
+}
+
+/// Image source, width and height as stored on tables
+type ImageInfo = (Option<String>, Option<u32>, Option<u32>);
+
+/// Parse the `source`, `width` and `height` attributes of an `<image>` element
+fn parse_image(e: &quick_xml::events::BytesStart) -> Result<ImageInfo, String> {
+    let mut image_source = None;
+    let mut image_width = None;
+    let mut image_height = None;
+
+    for attr in e.attributes() {
+        let attr = attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+        let value = attr
+            .unescape_value()
+            .map_err(|e| format!("Failed to parse attribute: {e}"))?;
+        match attr.key.as_ref() {
+            b"source" => image_source = Some(value.to_string()),
+            b"width" => image_width = value.parse().ok(),
+            b"height" => image_height = value.parse().ok(),
+            _ => {}
+        }
+    }
+
+    Ok((image_source, image_width, image_height))
+}
+
+/// Read the attributes of the `<map>` element of a TMX document
+fn read_map_header(tmx_content: &str) -> Result<TiledMap, String> {
+    use quick_xml::events::Event;
+
+    let mut reader = xml_reader(tmx_content);
+    let mut buf = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) if e.name().as_ref() == b"map" => return parse_map(&e),
+            Ok(Event::Eof) => return Err("No <map> element found".to_string()),
+            Err(e) => return Err(format!("XML parse error: {e}")),
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
+/// Read the `index`th `<tileset>` element of a TMX or TSX document, with its
+/// `<tileoffset>`, `<grid>` and `<transformations>` children
+fn read_tileset_header(content: &str, index: usize) -> Result<TiledTileset, String> {
+    use quick_xml::events::Event;
+
+    let mut reader = xml_reader(content);
+    let mut buf = Vec::new();
+    let mut tilesets_seen = 0;
+    let mut header: Option<TiledTileset> = None;
+    let mut depth = 0u32;
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                depth += 1;
+
+                match header.as_mut() {
+                    // Direct children of the tileset
+                    Some(tileset) if depth == 2 => parse_tileset_child(&e, tileset)?,
+                    Some(_) => {}
+                    None if e.name().as_ref() == b"tileset" => {
+                        if tilesets_seen == index {
+                            header = Some(parse_tileset(&e)?);
+                            depth = 1;
+                        }
+                        tilesets_seen += 1;
+                    }
+                    None => {}
+                }
+            }
+            Ok(Event::End(_)) => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    if let Some(tileset) = header {
+                        return Ok(tileset);
+                    }
+                }
+            }
+            Ok(Event::Eof) => {
+                return header.ok_or_else(|| format!("Tileset {index} not found"));
+            }
+            Err(e) => return Err(format!("XML parse error: {e}")),
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
+/// Read the `firstgid` of every top-level `<tileset>` in a TMX document, in order
+fn read_tileset_first_gids(tmx_content: &str) -> Result<Vec<u32>, String> {
+    use quick_xml::events::{BytesStart, Event};
+    use quick_xml::Reader;
+
+    fn first_gid_of(e: &BytesStart) -> Result<u32, String> {
+        let mut first_gid = 0u32;
+        for attr in e.attributes() {
+            let attr = attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+            if attr.key.as_ref() == b"firstgid" {
+                first_gid = std::str::from_utf8(&attr.value)
+                    .unwrap()
+                    .parse()
+                    .unwrap_or(0);
+            }
+        }
+        Ok(first_gid)
+    }
+
+    let mut reader = Reader::from_str(tmx_content);
+    let mut first_gids = Vec::new();
+    // Only direct children of <map> count; templates can contain tilesets too
+    let mut depth = 0u32;
+    let mut buf = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                if depth == 1 && e.name().as_ref() == b"tileset" {
+                    first_gids.push(first_gid_of(&e)?);
+                }
+                depth += 1;
+            }
+            Ok(Event::Empty(e)) if depth == 1 && e.name().as_ref() == b"tileset" => {
+                first_gids.push(first_gid_of(&e)?);
+            }
+            Ok(Event::End(_)) => depth = depth.saturating_sub(1),
+            Ok(Event::Eof) => break,
+            Err(e) => return Err(format!("XML parse error: {e}")),
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(first_gids)
+}
+
+// ============================================================================
+// Tiled JSON Loading
+// ============================================================================
+
+/// Load a Tiled JSON map (`.tmj`) from a string into SpacetimeDB tables
+///
+/// The JSON counterpart of [`load_tmx_map_from_str`]: it fills the same tables the same
+/// way and works in WASM modules. Tile data can be a plain array of GIDs or base64,
+/// optionally compressed, and infinite maps are read from their chunks.
+///
+/// External tilesets (`"source": "terrain.tsj"`) can't be read without their content and
+/// are stored without their metadata; use [`load_tmj_map_with_sources`] to supply them.
+/// Objects created from a template only get their own fields.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use spacetimedb::{reducer, ReducerContext};
+/// use spacetime_tiled::load_tmj_map_from_str;
+///
+/// #[reducer]
+/// pub fn upload_map(ctx: &ReducerContext, name: String, tmj: String) -> Result<(), String> {
+///     load_tmj_map_from_str(ctx, &name, &tmj)?;
+///     Ok(())
+/// }
+/// ```
+pub fn load_tmj_map_from_str(
+    ctx: &ReducerContext,
+    map_name: &str,
+    tmj_content: &str,
+) -> Result<u32, String> {
+    load_tmj_map_with_sources(ctx, map_name, "", tmj_content, &HashMap::new())
+}
+
+/// Load a Tiled JSON map from a string, reading external tilesets and templates from in-memory sources
+///
+/// The JSON counterpart of [`load_tmx_map_with_sources`], with the same `map_path` and
+/// `sources` keys and the same sharing of external tilesets between maps. External
+/// tilesets must be JSON (`.tsj`) and templates JSON (`.tj`) too; an XML `.tsx` or `.tx`
+/// file found in `sources` is an error. Tilesets and templates missing from `sources` are
+/// handled like the TMX loader does: a warning, then a tileset without metadata or an
+/// object with only its own fields.
+///
+/// Objects are merged with their template like Tiled does: fields and properties set on
+/// the object win, and everything else comes from the template.
+pub fn load_tmj_map_with_sources(
+    ctx: &ReducerContext,
+    map_name: &str,
+    map_path: &str,
+    tmj_content: &str,
+    sources: &HashMap<String, String>,
+) -> Result<u32, String> {
+    log::info!("Parsing TMJ map '{map_name}' from string");
+
+    let map: serde_json::Value =
+        serde_json::from_str(tmj_content).map_err(|e| format!("Failed to parse TMJ map: {e}"))?;
+
+    // Tilesets and templates are relative to the map
+    let map_dir = parent_dir(map_path);
+
+    let map_id = generate_map_id(ctx)?;
+    let map_header = parse_json_map(&map);
+    store_json_properties(ctx, "map", map_id as u64, &map)?;
+
+    for (tileset_index, tileset) in json_array(&map, "tilesets").iter().enumerate() {
+        store_json_tileset(ctx, map_id, tileset_index as u32, tileset, map_dir, sources)?;
+    }
+
+    // Templates are read once the map's tilesets are stored, so their GIDs can be remapped
+    let mut templates = HashMap::new();
+    load_json_templates(
+        ctx,
+        map_id,
+        map_dir,
+        json_array(&map, "layers"),
+        sources,
+        &mut templates,
+    )?;
+
+    let mut z_order = 0;
+    store_json_layers(
+        ctx,
+        map_id,
+        None,
+        json_array(&map, "layers"),
+        map_header.width,
+        &templates,
+        &mut z_order,
+    )?;
+
+    ctx.db
+        .tiled_map()
+        .try_insert(TiledMap {
+            map_id,
+            name: map_name.to_string(),
+            ..map_header
+        })
+        .map_err(|e| format!("Failed to insert map: {e}"))?;
+
+    log::info!("Successfully loaded map '{map_name}' from TMJ string");
+    Ok(map_id)
+}
+
+/// Store the layers of a TMJ `layers` array, recursing into group layers
+///
+/// `z_order` counts the layers stored so far, like in [`store_layers`].
+fn store_json_layers(
+    ctx: &ReducerContext,
+    map_id: u32,
+    parent_layer_id: Option<u32>,
+    layers: &[serde_json::Value],
+    map_width: u32,
+    templates: &HashMap<String, Option<serde_json::Value>>,
+    z_order: &mut u32,
+) -> Result<(), String> {
+    for value in layers {
+        let layer_type = match json_str(value, "type") {
+            Some("tilelayer") => "tile",
+            Some("objectgroup") => "object",
+            Some("imagelayer") => "image",
+            Some("group") => "group",
+            other => {
+                log::warn!("Skipping layer with unknown type {other:?}");
+                continue;
+            }
+        };
+
+        let layer_id = generate_layer_id(ctx)?;
+        ctx.db
+            .tiled_layer()
+            .try_insert(TiledLayer {
+                layer_id,
+                map_id,
+                z_order: *z_order,
+                parent_layer_id,
+                ..parse_json_layer(value, layer_type)
+            })
+            .map_err(|e| format!("Failed to insert layer: {e}"))?;
+        *z_order += 1;
+        store_json_properties(ctx, "layer", layer_id as u64, value)?;
+
+        match layer_type {
+            "tile" => {
+                let mut bounds = None;
+
+                // Infinite maps keep their tiles in chunks positioned in tile coordinates
+                if let Some(chunks) = value.get("chunks").and_then(serde_json::Value::as_array) {
+                    for chunk in chunks {
+                        let origin = (
+                            json_i32(chunk, "x").unwrap_or(0),
+                            json_i32(chunk, "y").unwrap_or(0),
+                            json_u32(chunk, "width").unwrap_or(0),
+                        );
+                        let tiles = decode_json_tile_data(chunk, value)?;
+                        store_tiles(ctx, layer_id, tiles, origin, &mut bounds)?;
+                    }
+                } else {
+                    let tiles = decode_json_tile_data(value, value)?;
+                    store_tiles(ctx, layer_id, tiles, (0, 0, map_width), &mut bounds)?;
+                }
+
+                set_layer_bounds(ctx, layer_id, bounds)?;
+            }
+            "object" => {
+                for object in json_array(value, "objects") {
+                    store_json_object(ctx, layer_id, object, templates)?;
+                }
+            }
+            "image" => {
+                let (image_source, image_width, image_height) = json_image(value);
+                ctx.db
+                    .tiled_image_layer()
+                    .try_insert(TiledImageLayer {
+                        layer_id,
+                        image_source,
+                        image_width,
+                        image_height,
+                        transparent_color: json_str(value, "transparentcolor").map(normalize_color),
+                        repeat_x: json_bool(value, "repeatx").unwrap_or(false),
+                        repeat_y: json_bool(value, "repeaty").unwrap_or(false),
+                    })
+                    .map_err(|e| format!("Failed to insert image layer: {e}"))?;
+            }
+            _ => store_json_layers(
+                ctx,
+                map_id,
+                Some(layer_id),
+                json_array(value, "layers"),
+                map_width,
+                templates,
+                z_order,
+            )?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Decode the `data` of a TMJ tile layer or chunk into raw GIDs (flip flags included)
+///
+/// `layer` holds the `encoding` and `compression` of the data.
+fn decode_json_tile_data(
+    value: &serde_json::Value,
+    layer: &serde_json::Value,
+) -> Result<Vec<u32>, String> {
+    match value.get("data") {
+        Some(serde_json::Value::Array(gids)) => Ok(gids
+            .iter()
+            .map(|gid| gid.as_u64().unwrap_or(0) as u32)
+            .collect()),
+        Some(serde_json::Value::String(text)) => decode_tile_data(
+            text,
+            json_str(layer, "encoding").or(Some("base64")),
+            json_str(layer, "compression"),
+        ),
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Store an object of a TMJ object layer, with its text and properties
+///
+/// `templates` holds the template objects read by [`load_json_templates`].
+fn store_json_object(
+    ctx: &ReducerContext,
+    layer_id: u32,
+    value: &serde_json::Value,
+    templates: &HashMap<String, Option<serde_json::Value>>,
+) -> Result<(), String> {
+    let merged;
+    let value = match json_str(value, "template").and_then(|path| templates.get(path)) {
+        Some(Some(template)) => {
+            merged = merge_json_template(template, value);
+            &merged
+        }
+        _ => value,
+    };
+
+    let object_id = generate_object_id(ctx)?;
+    ctx.db
+        .tiled_object()
+        .try_insert(TiledObject {
+            object_id,
+            layer_id,
+            ..parse_json_object(value)
+        })
+        .map_err(|e| format!("Failed to insert object: {e}"))?;
+
+    if let Some(text) = value.get("text") {
+        ctx.db
+            .tiled_object_text()
+            .try_insert(TiledObjectText {
+                object_id,
+                text: json_str(text, "text").unwrap_or_default().to_string(),
+                ..parse_json_text(text)
+            })
+            .map_err(|e| format!("Failed to insert object text: {e}"))?;
+    }
+
+    store_json_properties(ctx, "object", object_id, value)
+}
+
+/// Read a TMJ map's fields
+///
+/// `map_id` is left at 0 and `name` empty for the caller to fill in.
+fn parse_json_map(value: &serde_json::Value) -> TiledMap {
+    TiledMap {
+        map_id: 0,
+        name: String::new(),
+        width: json_u32(value, "width").unwrap_or(0),
+        height: json_u32(value, "height").unwrap_or(0),
+        tile_width: json_u32(value, "tilewidth").unwrap_or(0),
+        tile_height: json_u32(value, "tileheight").unwrap_or(0),
+        orientation: json_str(value, "orientation")
+            .unwrap_or("orthogonal")
+            .to_string(),
+        background_color: json_str(value, "backgroundcolor").map(normalize_color),
+        infinite: json_bool(value, "infinite").unwrap_or(false),
+        render_order: json_str(value, "renderorder")
+            .unwrap_or("right-down")
+            .to_string(),
+        stagger_axis: json_str(value, "staggeraxis").map(str::to_string),
+        stagger_index: json_str(value, "staggerindex").map(str::to_string),
+        hex_side_length: json_u32(value, "hexsidelength"),
+        parallax_origin_x: json_f32(value, "parallaxoriginx").unwrap_or(0.0),
+        parallax_origin_y: json_f32(value, "parallaxoriginy").unwrap_or(0.0),
+        class: json_str(value, "class").unwrap_or_default().to_string(),
+        // Tiled before 1.6 wrote the format version as a number
+        version: match value.get("version") {
+            Some(serde_json::Value::String(version)) => version.clone(),
+            Some(serde_json::Value::Number(version)) => version.to_string(),
+            _ => String::new(),
+        },
+        tiled_version: json_str(value, "tiledversion")
+            .unwrap_or_default()
+            .to_string(),
+        next_layer_id: json_u32(value, "nextlayerid").unwrap_or(0),
+        next_object_id: json_u32(value, "nextobjectid").unwrap_or(0),
+    }
+}
+
+/// Read the fields of a TMJ layer of the given type
+///
+/// `layer_id`, `map_id` and `z_order` are left at 0 and `parent_layer_id` empty for the
+/// caller to fill in.
+fn parse_json_layer(value: &serde_json::Value, layer_type: &str) -> TiledLayer {
+    TiledLayer {
+        layer_id: 0,
+        map_id: 0,
+        name: json_str(value, "name").unwrap_or_default().to_string(),
+        layer_type: layer_type.to_string(),
+        visible: json_bool(value, "visible").unwrap_or(true),
+        opacity: json_f32(value, "opacity").unwrap_or(1.0),
+        offset_x: json_i32(value, "offsetx").unwrap_or(0),
+        offset_y: json_i32(value, "offsety").unwrap_or(0),
+        z_order: 0,
+        parent_layer_id: None,
+        tint_color: json_str(value, "tintcolor").map(normalize_color),
+        parallax_x: json_f32(value, "parallaxx").unwrap_or(1.0),
+        parallax_y: json_f32(value, "parallaxy").unwrap_or(1.0),
+        class: json_str(value, "class").unwrap_or_default().to_string(),
+        min_x: None,
+        min_y: None,
+        max_x: None,
+        max_y: None,
+    }
+}
+
+/// Read the fields of a TMJ tileset, or of a tileset reference in a map's `tilesets`
+///
+/// `tileset_id`, `map_id`, `tileset_index` and `definition_tileset_id` are left at 0 and
+/// the image empty for the caller to fill in. `source` is the normalized path of an
+/// external tileset reference. The grid defaults to the tile size.
+fn parse_json_tileset(value: &serde_json::Value) -> TiledTileset {
+    let tile_width = json_u32(value, "tilewidth").unwrap_or(0);
+    let tile_height = json_u32(value, "tileheight").unwrap_or(0);
+    let offset = value.get("tileoffset").unwrap_or(&serde_json::Value::Null);
+    let grid = value.get("grid").unwrap_or(&serde_json::Value::Null);
+    let transformations = value
+        .get("transformations")
+        .unwrap_or(&serde_json::Value::Null);
+
+    TiledTileset {
+        tileset_id: 0,
+        map_id: 0,
+        tileset_index: 0,
+        first_gid: json_u32(value, "firstgid").unwrap_or(0),
+        name: json_str(value, "name").unwrap_or_default().to_string(),
+        tile_width,
+        tile_height,
+        tile_count: json_u32(value, "tilecount").unwrap_or(0),
+        columns: json_u32(value, "columns").unwrap_or(0),
+        spacing: json_u32(value, "spacing").unwrap_or(0),
+        margin: json_u32(value, "margin").unwrap_or(0),
+        tile_offset_x: json_i32(offset, "x").unwrap_or(0),
+        tile_offset_y: json_i32(offset, "y").unwrap_or(0),
+        object_alignment: json_str(value, "objectalignment")
+            .unwrap_or("unspecified")
+            .to_string(),
+        tile_render_size: json_str(value, "tilerendersize")
+            .unwrap_or("tile")
+            .to_string(),
+        fill_mode: json_str(value, "fillmode").unwrap_or("stretch").to_string(),
+        grid_orientation: json_str(grid, "orientation")
+            .unwrap_or("orthogonal")
+            .to_string(),
+        grid_width: json_u32(grid, "width").unwrap_or(tile_width),
+        grid_height: json_u32(grid, "height").unwrap_or(tile_height),
+        allow_flip_h: json_bool(transformations, "hflip").unwrap_or(false),
+        allow_flip_v: json_bool(transformations, "vflip").unwrap_or(false),
+        allow_rotate: json_bool(transformations, "rotate").unwrap_or(false),
+        prefer_untransformed: json_bool(transformations, "preferuntransformed").unwrap_or(false),
+        image_source: None,
+        image_width: None,
+        image_height: None,
+        source: json_str(value, "source").map(|source| resolve_path("", source)),
+        source_hash: None,
+        definition_tileset_id: 0,
+    }
+}
+
+/// Read the formatting fields of a TMJ object's `text`
+///
+/// `object_id` is left at 0 and `text` empty for the caller to fill in.
+fn parse_json_text(value: &serde_json::Value) -> TiledObjectText {
+    TiledObjectText {
+        object_id: 0,
+        text: String::new(),
+        font_family: json_str(value, "fontfamily")
+            .unwrap_or("sans-serif")
+            .to_string(),
+        pixel_size: json_u32(value, "pixelsize").unwrap_or(16),
+        wrap: json_bool(value, "wrap").unwrap_or(false),
+        color: json_str(value, "color").map_or_else(|| "#000000ff".to_string(), normalize_color),
+        bold: json_bool(value, "bold").unwrap_or(false),
+        italic: json_bool(value, "italic").unwrap_or(false),
+        underline: json_bool(value, "underline").unwrap_or(false),
+        strikeout: json_bool(value, "strikeout").unwrap_or(false),
+        kerning: json_bool(value, "kerning").unwrap_or(true),
+        halign: json_str(value, "halign").unwrap_or("left").to_string(),
+        valign: json_str(value, "valign").unwrap_or("top").to_string(),
+    }
+}
+
+/// Read a TMJ object, including its shape
+///
+/// `object_id` and `layer_id` are left at 0 for the caller to fill in.
+fn parse_json_object(value: &serde_json::Value) -> TiledObject {
+    // Tiled 1.9 renamed the object's `type` to `class`
+    let obj_type = json_str(value, "type").or_else(|| json_str(value, "class"));
+    let (gid, flip_h, flip_v, flip_d) = split_gid_flags(json_u32(value, "gid").unwrap_or(0));
+    let mut object = TiledObject {
+        object_id: 0,
+        layer_id: 0,
+        name: json_str(value, "name").unwrap_or_default().to_string(),
+        obj_type: obj_type.unwrap_or_default().to_string(),
+        x: json_f32(value, "x").unwrap_or(0.0),
+        y: json_f32(value, "y").unwrap_or(0.0),
+        width: json_f32(value, "width").unwrap_or(0.0),
+        height: json_f32(value, "height").unwrap_or(0.0),
+        rotation: json_f32(value, "rotation").unwrap_or(0.0),
+        visible: json_bool(value, "visible").unwrap_or(true),
+        shape: "rectangle".to_string(),
+        points: Vec::new(),
+        gid: (gid != 0).then_some(gid),
+        flip_h,
+        flip_v,
+        flip_d,
+    };
+
+    if json_bool(value, "ellipse").unwrap_or(false) {
+        object.shape = "ellipse".to_string();
+    } else if json_bool(value, "point").unwrap_or(false) {
+        object.shape = "point".to_string();
+        object.width = 0.0;
+        object.height = 0.0;
+    } else if let Some(points) = value.get("polygon") {
+        object.shape = "polygon".to_string();
+        object.points = json_points(points);
+    } else if let Some(points) = value.get("polyline") {
+        object.shape = "polyline".to_string();
+        object.points = json_points(points);
+    } else if value.get("text").is_some() {
+        object.shape = "text".to_string();
+    }
+
+    object
+}
+
+/// Read the templates used by the objects of a TMJ `layers` array, recursing into group layers
+///
+/// Templates are keyed by the object's `template` value and hold the template's object, with
+/// its GID moved to the map's copy of the template's tileset. Templates missing from
+/// `sources` are None.
+fn load_json_templates(
+    ctx: &ReducerContext,
+    map_id: u32,
+    map_dir: &str,
+    layers: &[serde_json::Value],
+    sources: &HashMap<String, String>,
+    templates: &mut HashMap<String, Option<serde_json::Value>>,
+) -> Result<(), String> {
+    for layer in layers {
+        load_json_templates(
+            ctx,
+            map_id,
+            map_dir,
+            json_array(layer, "layers"),
+            sources,
+            templates,
+        )?;
+
+        for object in json_array(layer, "objects") {
+            let Some(template) = json_str(object, "template") else {
+                continue;
+            };
+            if templates.contains_key(template) {
+                continue;
+            }
+
+            let path = resolve_path(map_dir, template);
+            let loaded = match find_source(sources, &path) {
+                Some(content) => Some(parse_json_template(ctx, map_id, &path, content)?),
+                None => {
+                    log::warn!(
+                        "Object template '{path}' not found in sources; objects using it only get their own fields"
+                    );
+                    None
+                }
+            };
+            templates.insert(template.to_string(), loaded);
+        }
+    }
+
+    Ok(())
+}
+
+/// Parse the `.tj` file at `path` for the objects of map `map_id`, returning its object
+///
+/// Like [`parse_template`], the template's tileset is looked up among the map's tilesets
+/// by source path, so tile templates get a GID that's valid in the map.
+fn parse_json_template(
+    ctx: &ReducerContext,
+    map_id: u32,
+    path: &str,
+    content: &str,
+) -> Result<serde_json::Value, String> {
+    if content.trim_start().starts_with('<') {
+        return Err(format!(
+            "Object template '{path}' is XML; TMJ maps need JSON templates (.tj)"
+        ));
+    }
+    let template: serde_json::Value = serde_json::from_str(content)
+        .map_err(|e| format!("Failed to parse object template '{path}': {e}"))?;
+
+    let mut object = template
+        .get("object")
+        .cloned()
+        .unwrap_or(serde_json::Value::Null);
+    let Some(fields) = object.as_object_mut() else {
+        return Err(format!("Object template '{path}' has no object"));
+    };
+
+    if let Some(raw_gid) = fields.remove("gid").and_then(|gid| gid.as_u64()) {
+        let raw_gid = raw_gid as u32;
+        let gid = raw_gid & GID_MASK;
+        let tileset = template.get("tileset").unwrap_or(&serde_json::Value::Null);
+        let first_gid = json_u32(tileset, "firstgid").unwrap_or(1);
+        let source = resolve_path(
+            parent_dir(path),
+            json_str(tileset, "source").unwrap_or_default(),
+        );
+
+        let map_tileset = ctx
+            .db
+            .tiled_tileset()
+            .map_id()
+            .filter(map_id)
+            .find(|tileset| tileset.source.as_deref() == Some(source.as_str()));
+
+        match map_tileset {
+            Some(map_tileset) if gid >= first_gid => {
+                let map_gid = (raw_gid & !GID_MASK) | (map_tileset.first_gid + gid - first_gid);
+                fields.insert("gid".to_string(), map_gid.into());
+            }
+            Some(_) => {}
+            None => log::warn!(
+                "Template '{path}' uses tileset '{source}', which the map doesn't reference"
+            ),
+        }
+    }
+
+    Ok(object)
+}
+
+/// Merge a TMJ object with its template object
+///
+/// The object's fields win over the template's, and its properties replace the
+/// template's properties of the same name. A class property set on both keeps the
+/// template's members the object doesn't set, like in [`apply_template_children`].
+fn merge_json_template(
+    template: &serde_json::Value,
+    object: &serde_json::Value,
+) -> serde_json::Value {
+    let mut merged = template.clone();
+    let (Some(fields), Some(overrides)) = (merged.as_object_mut(), object.as_object()) else {
+        return object.clone();
+    };
+
+    let mut properties = json_array(template, "properties").to_vec();
+    for property in json_array(object, "properties") {
+        let name = json_str(property, "name");
+        match properties
+            .iter_mut()
+            .find(|existing| json_str(existing, "name") == name)
+        {
+            Some(existing) => {
+                let mut merged = property.clone();
+                if json_str(existing, "type") == Some("class")
+                    && json_str(property, "type") == Some("class")
+                {
+                    if let (Some(members), Some(overrides)) =
+                        (existing.get("value"), property.get("value"))
+                    {
+                        merged["value"] = merge_json_members(members, overrides);
+                    }
+                }
+                *existing = merged;
+            }
+            None => properties.push(property.clone()),
+        }
+    }
+
+    for (key, value) in overrides {
+        fields.insert(key.clone(), value.clone());
+    }
+    fields.insert("properties".to_string(), properties.into());
+
+    merged
+}
+
+/// Overlay the members of a TMJ class value on those of its template, at any depth
+fn merge_json_members(
+    template: &serde_json::Value,
+    overrides: &serde_json::Value,
+) -> serde_json::Value {
+    let (Some(members), Some(overrides)) = (template.as_object(), overrides.as_object()) else {
+        return overrides.clone();
+    };
+
+    let mut merged = members.clone();
+    for (key, value) in overrides {
+        let member = match merged.get(key) {
+            // A nested class value, or a member written like a property
+            Some(existing) if existing.is_object() && value.is_object() => {
+                merge_json_members(existing, value)
+            }
+            _ => value.clone(),
+        };
+        merged.insert(key.clone(), member);
+    }
+
+    merged.into()
+}
+
+/// Parse the `polygon` or `polyline` array of a TMJ object
+fn json_points(value: &serde_json::Value) -> Vec<TiledPoint> {
+    value
+        .as_array()
+        .map(|points| {
+            points
+                .iter()
+                .map(|point| TiledPoint {
+                    x: json_f32(point, "x").unwrap_or(0.0),
+                    y: json_f32(point, "y").unwrap_or(0.0),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Store a tileset of a TMJ `tilesets` array, with its tiles and Wang sets
+///
+/// External tilesets are read from `sources` like in [`load_tmx_map_with_sources`].
+fn store_json_tileset(
+    ctx: &ReducerContext,
+    map_id: u32,
+    tileset_index: u32,
+    value: &serde_json::Value,
+    map_dir: &str,
+    sources: &HashMap<String, String>,
+) -> Result<(), String> {
+    let tileset = parse_json_tileset(value);
+
+    let Some(source) = tileset.source.as_deref() else {
+        return insert_json_tileset(ctx, map_id, tileset_index, tileset.first_gid, None, value);
+    };
+
+    // A reference to an external tileset: share it if another map already stored this
+    // version, otherwise read the .tsj file
+    let source = resolve_path(map_dir, source);
+    if let Some(tsj_content) = find_source(sources, &source) {
+        let source_hash = content_hash(tsj_content);
+        if let Some(definition) = find_external_tileset(ctx, &source, source_hash) {
+            let tileset_id = generate_tileset_id(ctx)?;
+            ctx.db
+                .tiled_tileset()
+                .try_insert(TiledTileset {
+                    tileset_id,
+                    map_id,
+                    tileset_index,
+                    first_gid: tileset.first_gid,
+                    ..definition
+                })
+                .map_err(|e| format!("Failed to insert tileset: {e}"))?;
+            return Ok(());
+        }
+
+        if tsj_content.trim_start().starts_with('<') {
+            return Err(format!(
+                "External tileset '{source}' is XML; TMJ maps need JSON tilesets (.tsj)"
+            ));
+        }
+        let definition: serde_json::Value = serde_json::from_str(tsj_content)
+            .map_err(|e| format!("Failed to parse tileset '{source}': {e}"))?;
+        return insert_json_tileset(
+            ctx,
+            map_id,
+            tileset_index,
+            tileset.first_gid,
+            Some((source, source_hash)),
+            &definition,
+        );
+    }
+
+    log::warn!("External tileset '{source}' not found in sources, storing it without metadata");
+    let tileset_id = generate_tileset_id(ctx)?;
+    ctx.db
+        .tiled_tileset()
+        .try_insert(TiledTileset {
+            tileset_id,
+            map_id,
+            tileset_index,
+            tile_width: 0,
+            tile_height: 0,
+            source: Some(source),
+            definition_tileset_id: tileset_id,
+            ..tileset
+        })
+        .map_err(|e| format!("Failed to insert tileset: {e}"))?;
+    Ok(())
+}
+
+/// Store a TMJ tileset with its metadata, from the map or from an external `.tsj` file
+fn insert_json_tileset(
+    ctx: &ReducerContext,
+    map_id: u32,
+    tileset_index: u32,
+    first_gid: u32,
+    source: Option<(String, u64)>,
+    value: &serde_json::Value,
+) -> Result<(), String> {
+    let tileset = parse_json_tileset(value);
+    let tileset_id = generate_tileset_id(ctx)?;
+    let (image_source, image_width, image_height) = json_image(value);
+    let (source, source_hash) = source.unzip();
+    ctx.db
+        .tiled_tileset()
+        .try_insert(TiledTileset {
+            tileset_id,
+            map_id,
+            tileset_index,
+            first_gid,
+            image_source,
+            image_width,
+            image_height,
+            source,
+            source_hash,
+            definition_tileset_id: tileset_id,
+            ..tileset
+        })
+        .map_err(|e| format!("Failed to insert tileset: {e}"))?;
+    store_json_properties(ctx, "tileset", tileset_id as u64, value)?;
+
+    for tile in json_array(value, "tiles") {
+        store_json_tileset_tile(ctx, tileset_id, tile)?;
+    }
+    for wang_set in json_array(value, "wangsets") {
+        store_json_wang_set(ctx, tileset_id, wang_set)?;
+    }
+
+    Ok(())
+}
+
+/// Store an entry of a TMJ tileset's `tiles` array, with its animation and collision shapes
+fn store_json_tileset_tile(
+    ctx: &ReducerContext,
+    tileset_id: u32,
+    value: &serde_json::Value,
+) -> Result<(), String> {
+    let local_id = json_u32(value, "id").unwrap_or(0);
+    let tileset_tile_id = tileset_tile_key(tileset_id, local_id);
+    let (image_source, image_width, image_height) = json_image(value);
+    // Without a sub-rectangle the tile uses the whole image
+    let rect_origin = image_source.is_some().then_some(0);
+
+    let row = TiledTilesetTile {
+        tileset_tile_id,
+        tileset_id,
+        local_id,
+        // Tiled 1.9 renamed "type" to "class"
+        tile_type: json_str(value, "type")
+            .or_else(|| json_str(value, "class"))
+            .unwrap_or_default()
+            .to_string(),
+        probability: json_f32(value, "probability").unwrap_or(1.0),
+        image_rect_x: json_u32(value, "x").or(rect_origin),
+        image_rect_y: json_u32(value, "y").or(rect_origin),
+        image_rect_width: json_u32(value, "width").or(image_width),
+        image_rect_height: json_u32(value, "height").or(image_height),
+        image_source,
+        image_width,
+        image_height,
+    };
+    let has_children = !json_array(value, "properties").is_empty()
+        || !json_array(value, "animation").is_empty()
+        || value
+            .get("objectgroup")
+            .is_some_and(|group| !json_array(group, "objects").is_empty());
+    if !has_tile_metadata(&row, has_children) {
+        return Ok(());
+    }
+
+    ctx.db
+        .tiled_tileset_tile()
+        .try_insert(row)
+        .map_err(|e| format!("Failed to insert tileset tile: {e}"))?;
+    store_json_properties(ctx, "tileset_tile", tileset_tile_id, value)?;
+
+    for (frame_index, frame) in json_array(value, "animation").iter().enumerate() {
+        insert_animation_frame(
+            ctx,
+            tileset_tile_id,
+            frame_index as u32,
+            json_u32(frame, "tileid").unwrap_or(0),
+            json_u32(frame, "duration").unwrap_or(0),
+        )?;
+    }
+
+    // Collision shapes drawn in Tiled's collision editor
+    if let Some(object_group) = value.get("objectgroup") {
+        for value in json_array(object_group, "objects") {
+            let object = parse_json_object(value);
+            let collision_id = generate_tile_collision_id(ctx)?;
+
+            ctx.db
+                .tiled_tile_collision()
+                .try_insert(TiledTileCollision {
+                    collision_id,
+                    tileset_tile_id,
+                    name: object.name,
+                    obj_type: object.obj_type,
+                    x: object.x,
+                    y: object.y,
+                    width: object.width,
+                    height: object.height,
+                    rotation: object.rotation,
+                    visible: object.visible,
+                    shape: object.shape,
+                    points: object.points,
+                })
+                .map_err(|e| format!("Failed to insert tile collision: {e}"))?;
+            store_json_properties(ctx, "tile_collision", collision_id, value)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Store an entry of a TMJ tileset's `wangsets` array, with its colors and tiles
+fn store_json_wang_set(
+    ctx: &ReducerContext,
+    tileset_id: u32,
+    value: &serde_json::Value,
+) -> Result<(), String> {
+    let wang_set_id = generate_wang_set_id(ctx)?;
+    ctx.db
+        .tiled_wang_set()
+        .try_insert(TiledWangSet {
+            wang_set_id,
+            tileset_id,
+            name: json_str(value, "name").unwrap_or_default().to_string(),
+            wang_type: json_str(value, "type").unwrap_or("mixed").to_string(),
+            // -1 means no representative tile
+            tile_id: json_u32(value, "tile"),
+        })
+        .map_err(|e| format!("Failed to insert wang set: {e}"))?;
+    store_json_properties(ctx, "wang_set", wang_set_id as u64, value)?;
+
+    for (color_index, color) in json_array(value, "colors").iter().enumerate() {
+        let wang_color_id = generate_wang_color_id(ctx)?;
+        ctx.db
+            .tiled_wang_color()
+            .try_insert(TiledWangColor {
+                wang_color_id,
+                wang_set_id,
+                color_index: color_index as u32 + 1,
+                name: json_str(color, "name").unwrap_or_default().to_string(),
+                color: normalize_color(json_str(color, "color").unwrap_or_default()),
+                tile_id: json_u32(color, "tile"),
+                probability: json_f32(color, "probability").unwrap_or(1.0),
+            })
+            .map_err(|e| format!("Failed to insert wang color: {e}"))?;
+        store_json_properties(ctx, "wang_color", wang_color_id as u64, color)?;
+    }
+
+    for wang_tile in json_array(value, "wangtiles") {
+        let wang_id = json_array(wang_tile, "wangid")
+            .iter()
+            .map(|color| color.as_u64().unwrap_or(0) as u8)
+            .collect();
+        insert_wang_tile(
+            ctx,
+            wang_set_id,
+            json_u32(wang_tile, "tileid").unwrap_or(0),
+            wang_id,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Store the `properties` array of a TMJ element
+fn store_json_properties(
+    ctx: &ReducerContext,
+    parent_type: &str,
+    parent_id: u64,
+    value: &serde_json::Value,
+) -> Result<(), String> {
+    for property in json_array(value, "properties") {
+        insert_json_property(
+            ctx,
+            TiledProperty {
+                property_id: 0,
+                parent_type: parent_type.to_string(),
+                parent_id,
+                key: json_str(property, "name").unwrap_or_default().to_string(),
+                value: String::new(),
+                value_type: json_str(property, "type").unwrap_or("string").to_string(),
+                property_type: json_str(property, "propertytype")
+                    .unwrap_or_default()
+                    .to_string(),
+            },
+            property.get("value"),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Convert the JSON value of a TMJ property to the string stored in `TiledProperty.value`
+///
+/// Colors are normalized like in TMX files. Class values are stored as their members.
+fn json_property_value(value: Option<&serde_json::Value>, value_type: &str) -> String {
+    match value {
+        Some(serde_json::Value::String(value)) if value_type == "color" => normalize_color(value),
+        Some(serde_json::Value::String(value)) => value.clone(),
+        Some(serde_json::Value::Bool(value)) => value.to_string(),
+        Some(serde_json::Value::Number(value)) => value.to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Get the type, custom type and value of a member of a TMJ class value
+///
+/// A member written like a property (`type`, `propertytype` and `value`) keeps its type.
+/// Otherwise the type is taken from the JSON value, since TMJ files usually leave it to
+/// the project's custom type definitions.
+fn json_member_type(member: &serde_json::Value) -> (&str, &str, Option<&serde_json::Value>) {
+    let member_type = json_str(member, "type").filter(|value_type| {
+        member.get("value").is_some()
+            && matches!(
+                *value_type,
+                "string" | "int" | "float" | "bool" | "color" | "file" | "object" | "class"
+            )
+    });
+
+    match member_type {
+        Some(value_type) => (
+            value_type,
+            json_str(member, "propertytype").unwrap_or_default(),
+            member.get("value"),
+        ),
+        None => {
+            let value_type = match member {
+                serde_json::Value::Bool(_) => "bool",
+                serde_json::Value::Number(number) if number.is_f64() => "float",
+                serde_json::Value::Number(_) => "int",
+                serde_json::Value::Object(_) => "class",
+                _ => "string",
+            };
+            (value_type, "", Some(member))
+        }
+    }
+}
+
+/// Store a TMJ property with its JSON value, followed by the members of class values
+fn insert_json_property(
+    ctx: &ReducerContext,
+    property: TiledProperty,
+    value: Option<&serde_json::Value>,
+) -> Result<(), String> {
+    let property_id = insert_property(
+        ctx,
+        TiledProperty {
+            value: json_property_value(value, &property.value_type),
+            ..property
+        },
+    )?;
+
+    if let Some(serde_json::Value::Object(members)) = value {
+        for (key, member) in members {
+            let (value_type, property_type, member) = json_member_type(member);
+
+            insert_json_property(
+                ctx,
+                TiledProperty {
+                    property_id: 0,
+                    parent_type: "property".to_string(),
+                    parent_id: property_id,
+                    key: key.clone(),
+                    value: String::new(),
+                    value_type: value_type.to_string(),
+                    property_type: property_type.to_string(),
+                },
+                member,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Image source and size of a TMJ tileset, tile or image layer
+fn json_image(value: &serde_json::Value) -> ImageInfo {
+    match json_str(value, "image").filter(|image| !image.is_empty()) {
+        Some(image) => (
+            Some(image.to_string()),
+            json_u32(value, "imagewidth"),
+            json_u32(value, "imageheight"),
+        ),
+        None => (None, None, None),
+    }
+}
+
+/// Get an array field of a TMJ object, or an empty slice if it's missing
+fn json_array<'a>(value: &'a serde_json::Value, key: &str) -> &'a [serde_json::Value] {
+    value
+        .get(key)
+        .and_then(serde_json::Value::as_array)
+        .map_or(&[], Vec::as_slice)
+}
+
+/// Get a string field of a TMJ object
+fn json_str<'a>(value: &'a serde_json::Value, key: &str) -> Option<&'a str> {
+    value.get(key).and_then(serde_json::Value::as_str)
+}
+
+/// Get a boolean field of a TMJ object
+fn json_bool(value: &serde_json::Value, key: &str) -> Option<bool> {
+    value.get(key).and_then(serde_json::Value::as_bool)
+}
+
+/// Get an unsigned integer field of a TMJ object, rounding fractional values; negative
+/// values count as missing
+fn json_u32(value: &serde_json::Value, key: &str) -> Option<u32> {
+    value
+        .get(key)
+        .and_then(serde_json::Value::as_f64)
+        .filter(|n| *n >= 0.0)
+        .map(|n| n.round() as u32)
+}
+
+/// Get an integer field of a TMJ object, rounding fractional values
+fn json_i32(value: &serde_json::Value, key: &str) -> Option<i32> {
+    value
+        .get(key)
+        .and_then(serde_json::Value::as_f64)
+        .map(|n| n.round() as i32)
+}
+
+/// Get a number field of a TMJ object
+fn json_f32(value: &serde_json::Value, key: &str) -> Option<f32> {
+    value
+        .get(key)
+        .and_then(serde_json::Value::as_f64)
+        .map(|n| n as f32)
+}
+
+// ============================================================================
+// World Loading
+// ============================================================================
+
+/// File name, world position and size (if the world file gives it) of a world's map
+type WorldMapEntry = (String, i32, i32, Option<u32>, Option<u32>);
+
+/// A `patterns` entry of a world file
+struct WorldPattern {
+    regex: regex_lite::Regex,
+    multiplier: (i32, i32),
+    offset: (i32, i32),
+    /// Map size, which defaults to the multipliers
+    map_size: (Option<u32>, Option<u32>),
+}
+
+/// Parse a `patterns` entry of a world file
+fn parse_world_pattern(pattern: &serde_json::Value) -> Result<WorldPattern, String> {
+    let regexp = json_str(pattern, "regexp").unwrap_or_default();
+    let regex = regex_lite::Regex::new(regexp)
+        .map_err(|e| format!("Failed to parse world pattern '{regexp}': {e}"))?;
+    if regex.captures_len() != 3 {
+        return Err(format!(
+            "World pattern '{regexp}' needs two capture groups for the map position"
+        ));
+    }
+
+    let multiplier_x = json_i32(pattern, "multiplierX").unwrap_or(1);
+    let multiplier_y = json_i32(pattern, "multiplierY").unwrap_or(1);
+
+    Ok(WorldPattern {
+        regex,
+        multiplier: (multiplier_x, multiplier_y),
+        offset: (
+            json_i32(pattern, "offsetX").unwrap_or(0),
+            json_i32(pattern, "offsetY").unwrap_or(0),
+        ),
+        map_size: (
+            json_u32(pattern, "mapWidth").or(u32::try_from(multiplier_x).ok()),
+            json_u32(pattern, "mapHeight").or(u32::try_from(multiplier_y).ok()),
+        ),
+    })
+}
+
+/// Get the world position of the map in `file_name`, or None if the pattern doesn't match it
+///
+/// The two capture groups are multiplied by the pattern's multipliers, then shifted by
+/// its offsets. A position that doesn't fit in an `i32` counts as no match.
+fn world_pattern_origin(pattern: &WorldPattern, file_name: &str) -> Option<(i32, i32)> {
+    let captures = pattern.regex.captures(file_name)?;
+    // Optional groups may not take part in the match
+    let x: i32 = captures.get(1)?.as_str().parse().ok()?;
+    let y: i32 = captures.get(2)?.as_str().parse().ok()?;
+
+    Some((
+        x.checked_mul(pattern.multiplier.0)?
+            .checked_add(pattern.offset.0)?,
+        y.checked_mul(pattern.multiplier.1)?
+            .checked_add(pattern.offset.1)?,
+    ))
+}
+
+/// List the maps of a parsed world file
+///
+/// `file_names` are the files next to the world file, in the order patterns are matched
+/// against them. A map listed under `maps` wins over a pattern, and an earlier pattern
+/// over a later one.
+fn world_map_entries(
+    world: &serde_json::Value,
+    file_names: &[String],
+) -> Result<Vec<WorldMapEntry>, String> {
+    let mut entries: Vec<WorldMapEntry> = Vec::new();
+
+    for map in json_array(world, "maps") {
+        entries.push((
+            resolve_path("", json_str(map, "fileName").unwrap_or_default()),
+            json_i32(map, "x").unwrap_or(0),
+            json_i32(map, "y").unwrap_or(0),
+            json_u32(map, "width"),
+            json_u32(map, "height"),
+        ));
+    }
+
+    for pattern in json_array(world, "patterns") {
+        let pattern = parse_world_pattern(pattern)?;
+
+        for file_name in file_names {
+            let Some((x, y)) = world_pattern_origin(&pattern, file_name) else {
+                continue;
+            };
+            if entries.iter().any(|entry| entry.0 == *file_name) {
+                continue;
+            }
+
+            entries.push((
+                file_name.clone(),
+                x,
+                y,
+                pattern.map_size.0,
+                pattern.map_size.1,
+            ));
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Size in pixels of the area a map covers, for a world file that doesn't give it
+///
+/// A fixed-size map covers its grid. An infinite map covers the tiles of its `layers`, from
+/// the smallest to the largest tile coordinate, or nothing if it has no tiles.
+fn world_map_size(map: &TiledMap, layers: &[TiledLayer]) -> Result<(u32, u32), String> {
+    let too_large = || format!("Map '{}' is too large to place in a world", map.name);
+
+    let (columns, rows) = if map.infinite {
+        let span = |min: Option<i32>, max: Option<i32>| match min.zip(max) {
+            Some((min, max)) => u32::try_from(max as i64 - min as i64 + 1).map_err(|_| too_large()),
+            None => Ok(0),
+        };
+        (
+            span(
+                layers.iter().filter_map(|layer| layer.min_x).min(),
+                layers.iter().filter_map(|layer| layer.max_x).max(),
+            )?,
+            span(
+                layers.iter().filter_map(|layer| layer.min_y).min(),
+                layers.iter().filter_map(|layer| layer.max_y).max(),
+            )?,
+        )
+    } else {
+        (map.width, map.height)
+    };
+
+    Ok((
+        columns.checked_mul(map.tile_width).ok_or_else(too_large)?,
+        rows.checked_mul(map.tile_height).ok_or_else(too_large)?,
+    ))
+}
+
+/// Load a Tiled world (`.world` file) and all of its maps
+///
+/// `sources` maps file paths, relative to the world file, to their content. It must hold
+/// every map of the world, plus the external tilesets and templates those maps use, like
+/// for [`load_tmx_map_with_sources`]. Maps ending in `.tmj` or `.json` are loaded with
+/// [`load_tmj_map_with_sources`]. An external tileset used by several maps is read for the
+/// first of them and shared by the rest.
+///
+/// Maps listed under `maps` are placed where the world file says. A `patterns` entry is
+/// matched against the file names in `sources` that sit next to the world file: its two
+/// capture groups are multiplied by `multiplierX`/`multiplierY` and shifted by
+/// `offsetX`/`offsetY` to give the map's position, like Tiled does. Files whose position
+/// doesn't fit in an `i32` are skipped.
+///
+/// Every map is stored under its file name, and `tiled_world_map` records where it is.
+/// Maps the world file gives no size for cover their own tiles; a map too large for its
+/// pixel size to fit in a `u32` is an error.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use std::collections::HashMap;
+/// use spacetimedb::{reducer, ReducerContext};
+/// use spacetime_tiled::load_world_from_str;
+///
+/// #[reducer]
+/// pub fn upload_world(
+///     ctx: &ReducerContext,
+///     world: String,
+///     town_tmx: String,
+///     forest_tmx: String,
+/// ) -> Result<(), String> {
+///     let sources = HashMap::from([
+///         ("town.tmx".to_string(), town_tmx),
+///         ("forest.tmx".to_string(), forest_tmx),
+///     ]);
+///     load_world_from_str(ctx, "overworld", &world, &sources)?;
+///     Ok(())
+/// }
+/// ```
+pub fn load_world_from_str(
+    ctx: &ReducerContext,
+    world_name: &str,
+    world_content: &str,
+    sources: &HashMap<String, String>,
+) -> Result<u32, String> {
+    log::info!("Parsing world '{world_name}' from string");
+
+    let world: serde_json::Value =
+        serde_json::from_str(world_content).map_err(|e| format!("Failed to parse world: {e}"))?;
+
+    // Patterns only match maps in the world file's own directory
+    let mut file_names: Vec<String> = sources
+        .keys()
+        .map(|key| resolve_path("", key))
+        .filter(|file_name| !file_name.contains('/'))
+        .collect();
+    file_names.sort();
+
+    let entries = world_map_entries(&world, &file_names)?;
+
+    let world_id = generate_world_id(ctx)?;
+    ctx.db
+        .tiled_world()
+        .try_insert(TiledWorld {
+            world_id,
+            name: world_name.to_string(),
+        })
+        .map_err(|e| format!("Failed to insert world: {e}"))?;
+
+    for (file_name, x, y, width, height) in entries {
+        let content = find_source(sources, &file_name)
+            .ok_or_else(|| format!("World map '{file_name}' not found in sources"))?;
+
+        let map_id = if file_name.ends_with(".tmj") || file_name.ends_with(".json") {
+            load_tmj_map_with_sources(ctx, &file_name, &file_name, content, sources)?
+        } else {
+            load_tmx_map_with_sources(ctx, &file_name, &file_name, content, sources)?
+        };
+
+        // Without a size in the world file, the map covers its own tiles
+        let (width, height) = match (width, height) {
+            (Some(width), Some(height)) => (width, height),
+            _ => {
+                let map = ctx
+                    .db
+                    .tiled_map()
+                    .map_id()
+                    .find(map_id)
+                    .ok_or_else(|| format!("Map {map_id} not found"))?;
+                let layers: Vec<_> = ctx.db.tiled_layer().map_id().filter(map_id).collect();
+                let (map_width, map_height) = world_map_size(&map, &layers)?;
+                (width.unwrap_or(map_width), height.unwrap_or(map_height))
+            }
+        };
+
+        ctx.db
+            .tiled_world_map()
+            .try_insert(TiledWorldMap {
+                map_id,
+                world_id,
+                file_name,
+                x,
+                y,
+                width,
+                height,
+            })
+            .map_err(|e| format!("Failed to insert world map: {e}"))?;
+    }
+
+    log::info!("Successfully loaded world '{world_name}' from string");
+    Ok(world_id)
+}
+
+// ============================================================================
+// Query Helpers
+// ============================================================================
+
+/// Resolve a global tile ID to the tileset it belongs to
+///
+/// Flip flags are ignored. Returns `(tileset_id, local_tile_id)`, or `None` for the
+/// empty tile (GID 0) and GIDs not covered by any tileset of the map, including GIDs
+/// past the last tile of a tileset image.
+///
+/// # Example
+/// ```rust,no_run
+/// # use spacetimedb::ReducerContext;
+/// # use spacetime_tiled::*;
+/// # fn example(ctx: &ReducerContext, tile_id: u64) {
+/// let tile = ctx.db.tiled_tile().tile_id().find(tile_id).unwrap();
+/// let layer = ctx.db.tiled_layer().layer_id().find(tile.layer_id).unwrap();
+/// if let Some((tileset_id, local_id)) = resolve_gid(ctx, layer.map_id, tile.gid) {
+///     log::info!("Tile {local_id} of tileset {tileset_id}");
+/// }
+/// # }
+/// ```
+pub fn resolve_gid(ctx: &ReducerContext, map_id: u32, gid: u32) -> Option<(u32, u32)> {
+    let gid = gid & GID_MASK;
+    if gid == 0 {
+        return None;
+    }
+
+    // Tilesets are sorted by firstgid in a map, so the owner is the last one starting at or below gid
+    ctx.db
+        .tiled_tileset()
+        .map_id()
+        .filter(map_id)
+        .filter(|tileset| tileset.first_gid > 0 && tileset.first_gid <= gid)
+        .max_by_key(|tileset| tileset.first_gid)
+        // Image collection tile IDs can skip numbers, and tilesets stored without their
+        // metadata have no tile count, so only tileset images bound the local ID
+        .filter(|tileset| {
+            tileset.tile_count == 0
+                || tileset.image_source.is_none()
+                || gid - tileset.first_gid < tileset.tile_count
+        })
+        .map(|tileset| (tileset.tileset_id, gid - tileset.first_gid))
+}
+
+/// Look up the metadata of a tile inside a tileset
+///
+/// Returns `None` if the tileset has no `<tile>` entry for `local_id`. Combine with
+/// [`resolve_gid`] to go from a placed tile to its class, properties or image.
+///
+/// This and the other tileset tile helpers accept any map's `tileset_id` and follow
+/// shared external tilesets to their `definition_tileset_id`.
+pub fn find_tileset_tile(
+    ctx: &ReducerContext,
+    tileset_id: u32,
+    local_id: u32,
+) -> Option<TiledTilesetTile> {
+    ctx.db
+        .tiled_tileset_tile()
+        .tileset_tile_id()
+        .find(tileset_tile_key(
+            definition_tileset_id(ctx, tileset_id),
+            local_id,
+        ))
+}
+
+/// Get the image a tile is drawn from and its rectangle in that image
+///
+/// Returns `(image_source, x, y, width, height)` in pixels. Tiles of image collection
+/// tilesets use their own image (or the part of it given by their sub-rectangle);
+/// other tiles are cut from the tileset image using its columns, spacing and margin.
+/// Returns `None` if the tile has no image.
+pub fn tile_image_rect(
+    ctx: &ReducerContext,
+    tileset_id: u32,
+    local_id: u32,
+) -> Option<(String, u32, u32, u32, u32)> {
+    if let Some(tile) = find_tileset_tile(ctx, tileset_id, local_id) {
+        if let Some(image_source) = tile.image_source {
+            return Some((
+                image_source,
+                tile.image_rect_x.unwrap_or(0),
+                tile.image_rect_y.unwrap_or(0),
+                tile.image_rect_width.or(tile.image_width)?,
+                tile.image_rect_height.or(tile.image_height)?,
+            ));
+        }
+    }
+
+    let tileset = ctx.db.tiled_tileset().tileset_id().find(tileset_id)?;
+    if tileset.columns == 0 || local_id >= tileset.tile_count {
+        return None;
+    }
+
+    let column = local_id % tileset.columns;
+    let row = local_id / tileset.columns;
+    Some((
+        tileset.image_source?,
+        tileset.margin + column * (tileset.tile_width + tileset.spacing),
+        tileset.margin + row * (tileset.tile_height + tileset.spacing),
+        tileset.tile_width,
+        tileset.tile_height,
+    ))
+}
+
+/// Get the custom properties of an element
+///
+/// `parent_type` and `parent_id` are as stored on [`TiledProperty`], e.g.
+/// `get_properties(ctx, "object", object.object_id)`.
+pub fn get_properties(
+    ctx: &ReducerContext,
+    parent_type: &str,
+    parent_id: u64,
+) -> Vec<TiledProperty> {
+    ctx.db
+        .tiled_property()
+        .parent_id()
+        .filter(parent_id)
+        .filter(|property| property.parent_type == parent_type)
+        .collect()
+}
+
+/// Find a property by key, following the members of class properties for longer paths
+///
+/// `find_property(ctx, "object", object_id, &["loot", "rare", "item"])` returns the `item`
+/// member of the `rare` member of the object's `loot` class property.
+pub fn find_property(
+    ctx: &ReducerContext,
+    parent_type: &str,
+    parent_id: u64,
+    path: &[&str],
+) -> Option<TiledProperty> {
+    let (first, members) = path.split_first()?;
+    let mut property = get_properties(ctx, parent_type, parent_id)
+        .into_iter()
+        .find(|property| property.key == *first)?;
+
+    for key in members {
+        property = get_properties(ctx, "property", property.property_id)
+            .into_iter()
+            .find(|member| member.key == *key)?;
+    }
+
+    Some(property)
+}
+
+/// Look up the tileset tile a tile object shows, with the tile's properties
+///
+/// Returns `None` if the object isn't a tile object or its tile has no `<tile>`
+/// entry in the tileset (use [`resolve_gid`] with the object's `gid` for those).
+pub fn object_tileset_tile(
+    ctx: &ReducerContext,
+    object_id: u64,
+) -> Option<(TiledTilesetTile, Vec<TiledProperty>)> {
+    let object = ctx.db.tiled_object().object_id().find(object_id)?;
+    let layer = ctx.db.tiled_layer().layer_id().find(object.layer_id)?;
+    let (tileset_id, local_id) = resolve_gid(ctx, layer.map_id, object.gid?)?;
+    let tileset_tile = find_tileset_tile(ctx, tileset_id, local_id)?;
+    let properties = get_properties(ctx, "tileset_tile", tileset_tile.tileset_tile_id);
+
+    Some((tileset_tile, properties))
+}
+
+/// Get the collision shapes drawn on a tileset tile
+///
+/// Returns an empty list if the tile has no collision shapes.
+pub fn tile_collision_shapes(
+    ctx: &ReducerContext,
+    tileset_id: u32,
+    local_id: u32,
+) -> Vec<TiledTileCollision> {
+    ctx.db
+        .tiled_tile_collision()
+        .tileset_tile_id()
+        .filter(tileset_tile_key(
+            definition_tileset_id(ctx, tileset_id),
+            local_id,
+        ))
+        .collect()
+}
+
+/// Get the Wang colors (terrains) a tileset tile is painted with
+///
+/// Looks at every Wang set of the tileset and returns each color used by any edge
+/// or corner of the tile, ordered by Wang set and color index. Combine with
+/// [`resolve_gid`] to find the terrain of a placed tile.
+pub fn tile_wang_colors(
+    ctx: &ReducerContext,
+    tileset_id: u32,
+    local_id: u32,
+) -> Vec<TiledWangColor> {
+    let tileset_id = definition_tileset_id(ctx, tileset_id);
+    let mut colors = Vec::new();
+
+    for wang_set in ctx.db.tiled_wang_set().tileset_id().filter(tileset_id) {
+        let color_indices: Vec<u32> = ctx
+            .db
+            .tiled_wang_tile()
+            .wang_set_id()
+            .filter(wang_set.wang_set_id)
+            .filter(|wang_tile| wang_tile.local_id == local_id)
+            .flat_map(|wang_tile| wang_tile.wang_id)
+            .filter(|&index| index != 0)
+            .map(u32::from)
+            .collect();
+
+        let mut set_colors: Vec<_> = ctx
+            .db
+            .tiled_wang_color()
+            .wang_set_id()
+            .filter(wang_set.wang_set_id)
+            .filter(|color| color_indices.contains(&color.color_index))
+            .collect();
+        set_colors.sort_by_key(|color| color.color_index);
+        colors.extend(set_colors);
+    }
+
+    colors
+}
+
+/// Get the frames of an animated tile, ordered by frame index
+///
+/// Returns an empty list if the tile isn't animated.
+pub fn tile_animation_frames(
+    ctx: &ReducerContext,
+    tileset_id: u32,
+    local_id: u32,
+) -> Vec<TiledTileAnimation> {
+    let mut frames: Vec<_> = ctx
+        .db
+        .tiled_tile_animation()
+        .tileset_tile_id()
+        .filter(tileset_tile_key(
+            definition_tileset_id(ctx, tileset_id),
+            local_id,
+        ))
+        .collect();
+    frames.sort_by_key(|frame| frame.frame_index);
+    frames
+}
+
+/// Work out which frame of an animated tile is showing at a point in time
+///
+/// `elapsed_ms` is the time since the animation started (for example milliseconds
+/// since the Unix epoch, if every animation started at the same time). The
+/// animation loops. Returns the local tile ID to draw, or `None` if the tile isn't
+/// animated.
+pub fn animation_frame_at(
+    ctx: &ReducerContext,
+    tileset_id: u32,
+    local_id: u32,
+    elapsed_ms: u64,
+) -> Option<u32> {
+    let frames = tile_animation_frames(ctx, tileset_id, local_id);
+    let total_ms: u64 = frames.iter().map(|frame| frame.duration_ms as u64).sum();
+    if total_ms == 0 {
+        return frames.first().map(|frame| frame.frame_tile_id);
+    }
+
+    let mut remaining = elapsed_ms % total_ms;
+    for frame in &frames {
+        if remaining < frame.duration_ms as u64 {
+            return Some(frame.frame_tile_id);
+        }
+        remaining -= frame.duration_ms as u64;
+    }
+
+    None
+}
+
+/// Get the layers inside a group layer, in drawing order
+pub fn child_layers(ctx: &ReducerContext, layer_id: u32) -> Vec<TiledLayer> {
+    let Some(group) = ctx.db.tiled_layer().layer_id().find(layer_id) else {
+        return Vec::new();
+    };
+
+    let mut layers: Vec<_> = ctx
+        .db
+        .tiled_layer()
+        .map_id()
+        .filter(group.map_id)
+        .filter(|layer| layer.parent_layer_id == Some(layer_id))
+        .collect();
+    layers.sort_by_key(|layer| layer.z_order);
+    layers
+}
+
+/// Get the offset of a layer in pixels, including the offsets of its group layers
+///
+/// Returns `None` if the layer doesn't exist.
+pub fn effective_layer_offset(ctx: &ReducerContext, layer_id: u32) -> Option<(i32, i32)> {
+    let layers = layer_with_ancestors(ctx, layer_id);
+    (!layers.is_empty()).then(|| {
+        layers.iter().fold((0, 0), |(x, y), layer| {
+            (x + layer.offset_x, y + layer.offset_y)
+        })
+    })
+}
+
+/// Get the opacity a layer is drawn with: its own opacity times that of its group layers
+///
+/// Returns `None` if the layer doesn't exist.
+pub fn effective_layer_opacity(ctx: &ReducerContext, layer_id: u32) -> Option<f32> {
+    let layers = layer_with_ancestors(ctx, layer_id);
+    (!layers.is_empty()).then(|| layers.iter().map(|layer| layer.opacity).product())
+}
+
+/// Check whether a layer is shown: it and all of its group layers must be visible
+///
+/// Returns `None` if the layer doesn't exist.
+pub fn effective_layer_visibility(ctx: &ReducerContext, layer_id: u32) -> Option<bool> {
+    let layers = layer_with_ancestors(ctx, layer_id);
+    (!layers.is_empty()).then(|| layers.iter().all(|layer| layer.visible))
+}
+
+/// Get the parallax factors of a layer, multiplied by those of its group layers
+///
+/// Returns `None` if the layer doesn't exist.
+pub fn effective_layer_parallax(ctx: &ReducerContext, layer_id: u32) -> Option<(f32, f32)> {
+    let layers = layer_with_ancestors(ctx, layer_id);
+    (!layers.is_empty()).then(|| {
+        layers.iter().fold((1.0, 1.0), |(x, y), layer| {
+            (x * layer.parallax_x, y * layer.parallax_y)
+        })
+    })
+}
+
+/// Get the tint color a layer is drawn with, as `#rrggbbaa`
+///
+/// Tints of nested group layers are multiplied together like Tiled does. Returns
+/// `None` if neither the layer nor its group layers are tinted.
+pub fn effective_layer_tint(ctx: &ReducerContext, layer_id: u32) -> Option<String> {
+    let tint = layer_with_ancestors(ctx, layer_id)
+        .iter()
+        .filter_map(|layer| layer.tint_color.as_deref().and_then(parse_color))
+        .reduce(|tint, other| {
+            std::array::from_fn(|i| ((tint[i] as u32 * other[i] as u32 + 127) / 255) as u8)
+        })?;
+
+    Some(format!(
+        "#{:02x}{:02x}{:02x}{:02x}",
+        tint[0], tint[1], tint[2], tint[3]
+    ))
+}
+
+/// Find the map of a world that covers a world-space position, in pixels
+///
+/// A map covers `x..x + width` and `y..y + height`. Where maps overlap, the one with
+/// the lowest map ID wins.
+pub fn world_map_at(ctx: &ReducerContext, world_id: u32, x: f32, y: f32) -> Option<TiledWorldMap> {
+    ctx.db
+        .tiled_world_map()
+        .world_id()
+        .filter(world_id)
+        .filter(|map| world_map_covers(map, x, y))
+        .min_by_key(|map| map.map_id)
+}
+
+/// Check whether a world map covers a world-space position, in pixels
+fn world_map_covers(map: &TiledWorldMap, x: f32, y: f32) -> bool {
+    x >= map.x as f32
+        && y >= map.y as f32
+        && x < map.x as f32 + map.width as f32
+        && y < map.y as f32 + map.height as f32
+}
+
+/// Get the maps of the same world that border a map
+///
+/// Maps that touch the map at an edge or a corner count, as do maps overlapping it.
+/// Returns nothing if the map wasn't loaded as part of a world.
+pub fn adjacent_world_maps(ctx: &ReducerContext, map_id: u32) -> Vec<TiledWorldMap> {
+    let Some(map) = ctx.db.tiled_world_map().map_id().find(map_id) else {
+        return Vec::new();
+    };
+
+    let mut maps: Vec<_> = ctx
+        .db
+        .tiled_world_map()
+        .world_id()
+        .filter(map.world_id)
+        .filter(|other| other.map_id != map_id && world_maps_touch(&map, other))
+        .collect();
+    maps.sort_by_key(|other| other.map_id);
+    maps
+}
+
+/// Check whether two world maps touch at an edge or a corner, or overlap
+fn world_maps_touch(map: &TiledWorldMap, other: &TiledWorldMap) -> bool {
+    other.x as i64 <= map.x as i64 + map.width as i64
+        && map.x as i64 <= other.x as i64 + other.width as i64
+        && other.y as i64 <= map.y as i64 + map.height as i64
+        && map.y as i64 <= other.y as i64 + other.height as i64
+}
+
+/// Get a layer followed by its group layers, innermost first
+fn layer_with_ancestors(ctx: &ReducerContext, layer_id: u32) -> Vec<TiledLayer> {
+    let mut layers: Vec<TiledLayer> = Vec::new();
+    let mut next = Some(layer_id);
+
+    while let Some(layer) = next.and_then(|id| ctx.db.tiled_layer().layer_id().find(id)) {
+        next = layer.parent_layer_id;
+        layers.push(layer);
+
+        // Guard against a malformed parent chain
+        if next.is_some_and(|id| layers.iter().any(|layer| layer.layer_id == id)) {
+            break;
+        }
+    }
+
+    layers
+}
+
+/// Parse a stored `#rrggbbaa` color into its channels
+fn parse_color(color: &str) -> Option<[u8; 4]> {
+    let hex = color.strip_prefix('#')?;
+    if hex.len() != 8 {
+        return None;
+    }
+
+    let mut channels = [0u8; 4];
+    for (i, channel) in channels.iter_mut().enumerate() {
+        *channel = u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(channels)
+}
+
+// ============================================================================
+// Tile Data Decoding
+// ============================================================================
+
+const FLIPPED_HORIZONTALLY_FLAG: u32 = 0x80000000;
+const FLIPPED_VERTICALLY_FLAG: u32 = 0x40000000;
+const FLIPPED_DIAGONALLY_FLAG: u32 = 0x20000000;
+/// Set on hexagonal maps for tiles rotated by 120 degrees
+const ROTATED_HEXAGONAL_120_FLAG: u32 = 0x10000000;
+const GID_MASK: u32 = !(FLIPPED_HORIZONTALLY_FLAG
+    | FLIPPED_VERTICALLY_FLAG
+    | FLIPPED_DIAGONALLY_FLAG
+    | ROTATED_HEXAGONAL_120_FLAG);
+
+/// Split a raw GID from tile data into the tile ID and its flip flags
+///
+/// Returns `(gid, flip_h, flip_v, flip_d)`. The hexagonal rotation flag isn't stored,
+/// but it's still cleared from the GID.
+fn split_gid_flags(gid_with_flags: u32) -> (u32, bool, bool, bool) {
+    (
+        gid_with_flags & GID_MASK,
+        gid_with_flags & FLIPPED_HORIZONTALLY_FLAG != 0,
+        gid_with_flags & FLIPPED_VERTICALLY_FLAG != 0,
+        gid_with_flags & FLIPPED_DIAGONALLY_FLAG != 0,
+    )
+}
+
+/// Get the raw GID (flip flags included) of a `<tile>` in XML-encoded tile data
+///
+/// A `<tile/>` without a `gid` is an empty cell.
+fn parse_data_tile(e: &quick_xml::events::BytesStart) -> Result<u32, String> {
+    let mut gid = 0u32;
+
+    for attr in e.attributes() {
+        let attr = attr.map_err(|e| format!("Failed to parse attribute: {e}"))?;
+        if attr.key.as_ref() == b"gid" {
+            gid = std::str::from_utf8(&attr.value)
+                .unwrap()
+                .parse()
+                .unwrap_or(0);
+        }
+    }
+
+    Ok(gid)
+}
+
+/// Decode the text content of a `<data>` element into raw GIDs (flip flags included)
+///
+/// Handles `encoding="csv"` and `encoding="base64"`, optionally compressed with zlib,
+/// gzip or zstd. Decoded base64 data is a sequence of little-endian u32 values, one per tile.
+fn decode_tile_data(
+    text: &str,
+    encoding: Option<&str>,
+    compression: Option<&str>,
+) -> Result<Vec<u32>, String> {
+    match encoding {
+        Some("csv") => Ok(text
+            .split(',')
+            .filter_map(|s| s.trim().parse().ok())
+            .collect()),
+        Some("base64") => {
+            use base64::Engine;
+
+            // Tiled may wrap the payload in whitespace or newlines
+            let payload: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(payload)
+                .map_err(|e| format!("Failed to decode base64 tile data: {e}"))?;
+            let bytes = decompress_tile_data(bytes, compression)?;
+
+            if bytes.len() % 4 != 0 {
+                return Err(format!(
+                    "Base64 tile data is {} bytes, expected a multiple of 4",
+                    bytes.len()
+                ));
+            }
+
+            Ok(bytes
+                .chunks_exact(4)
+                .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect())
+        }
+        Some(other) => Err(format!("Unsupported tile data encoding '{other}'")),
+        None => Err("Tile data without an encoding must be <tile> elements".to_string()),
+    }
+}
+
+/// Decompress base64-decoded tile data according to the `compression` attribute
+///
+/// All decoders are pure Rust so this works in the wasm32 SpacetimeDB target.
+fn decompress_tile_data(bytes: Vec<u8>, compression: Option<&str>) -> Result<Vec<u8>, String> {
+    use std::io::Read;
+
+    let mut out = Vec::new();
+    match compression {
+        None | Some("") => return Ok(bytes),
+        Some("zlib") => flate2::read::ZlibDecoder::new(bytes.as_slice())
+            .read_to_end(&mut out)
+            .map_err(|e| format!("Failed to decompress zlib tile data: {e}"))?,
+        Some("gzip") => flate2::read::GzDecoder::new(bytes.as_slice())
+            .read_to_end(&mut out)
+            .map_err(|e| format!("Failed to decompress gzip tile data: {e}"))?,
+        Some("zstd") => ruzstd::decoding::StreamingDecoder::new(bytes.as_slice())
+            .map_err(|e| format!("Failed to decompress zstd tile data: {e}"))?
+            .read_to_end(&mut out)
+            .map_err(|e| format!("Failed to decompress zstd tile data: {e}"))?,
+        Some(other) => return Err(format!("Unsupported tile data compression '{other}'")),
+    };
+
+    Ok(out)
+}
+
+// ============================================================================
+// ID Generation Helpers
+// ============================================================================
+
+fn generate_map_id(ctx: &ReducerContext) -> Result<u32, String> {
+    Ok(ctx.db.tiled_map().count() as u32)
+}
+
+fn generate_layer_id(ctx: &ReducerContext) -> Result<u32, String> {
+    Ok(ctx.db.tiled_layer().count() as u32)
+}
+
+fn generate_tileset_id(ctx: &ReducerContext) -> Result<u32, String> {
+    Ok(ctx.db.tiled_tileset().count() as u32)
+}
+
+fn generate_tile_id(ctx: &ReducerContext) -> Result<u64, String> {
+    Ok(ctx.db.tiled_tile().count())
+}
+
+fn generate_object_id(ctx: &ReducerContext) -> Result<u64, String> {
+    Ok(ctx.db.tiled_object().count())
+}
+
+fn generate_property_id(ctx: &ReducerContext) -> Result<u64, String> {
+    Ok(ctx.db.tiled_property().count())
+}
+
+fn generate_tile_animation_id(ctx: &ReducerContext) -> Result<u64, String> {
+    Ok(ctx.db.tiled_tile_animation().count())
+}
+
+fn generate_tile_collision_id(ctx: &ReducerContext) -> Result<u64, String> {
+    Ok(ctx.db.tiled_tile_collision().count())
+}
+
+fn generate_wang_set_id(ctx: &ReducerContext) -> Result<u32, String> {
+    Ok(ctx.db.tiled_wang_set().count() as u32)
+}
+
+fn generate_wang_color_id(ctx: &ReducerContext) -> Result<u32, String> {
+    Ok(ctx.db.tiled_wang_color().count() as u32)
+}
+
+fn generate_wang_tile_id(ctx: &ReducerContext) -> Result<u64, String> {
+    Ok(ctx.db.tiled_wang_tile().count())
+}
+
+fn generate_world_id(ctx: &ReducerContext) -> Result<u32, String> {
+    Ok(ctx.db.tiled_world().count() as u32)
+}
+
+// Note: This library only provides table definitions and the load_tmx_map() function.
+// You should define your own reducers in your SpacetimeDB module that use these tables.
+// See examples/simple_game/server/src/lib.rs for examples of reducers you can create.
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine;
+    use std::io::Write;
+
+    /// GIDs with a flip flag, as Tiled writes them
+    const GIDS: [u32; 4] = [0, 1, 0x80000002, 42];
+
+    fn tile_bytes() -> Vec<u8> {
+        GIDS.iter().flat_map(|gid| gid.to_le_bytes()).collect()
+    }
+
+    fn base64(bytes: &[u8]) -> String {
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    }
+
+    /// The first element of an XML fragment
+    fn start(xml: &str) -> quick_xml::events::BytesStart<'static> {
+        let mut reader = xml_reader(xml);
+        loop {
+            match reader.read_event().unwrap() {
+                quick_xml::events::Event::Start(e) => return e.into_owned(),
+                quick_xml::events::Event::Eof => panic!("no element in {xml}"),
+                _ => {}
+            }
+        }
+    }
+
+    fn property(
+        id: u64,
+        parent: (&str, u64),
+        key: &str,
+        value: &str,
+        value_type: &str,
+    ) -> TiledProperty {
+        TiledProperty {
+            property_id: id,
+            parent_type: parent.0.to_string(),
+            parent_id: parent.1,
+            key: key.to_string(),
+            value: value.to_string(),
+            value_type: value_type.to_string(),
+            property_type: String::new(),
+        }
+    }
+
+    /// Parse a TMX map at `maps/test.tmx` with the string loader, without a database
+    fn parse_rows(tmx: &str, sources: &[(&str, &str)]) -> MapRows {
+        let sources = sources
+            .iter()
+            .map(|(path, content)| (path.to_string(), content.to_string()))
+            .collect();
+        parse_tmx_rows(
+            "maps/test.tmx",
+            tmx,
+            &sources,
+            FirstIds::default(),
+            |_, _| None,
+        )
+        .unwrap()
+    }
+
+    /// `(parent_type, parent_id, key, value)` of every property, in storing order
+    fn property_links(rows: &MapRows) -> Vec<(&str, u64, &str, &str)> {
+        rows.properties
+            .iter()
+            .map(|p| {
+                (
+                    p.parent_type.as_str(),
+                    p.parent_id,
+                    p.key.as_str(),
+                    p.value.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    const CHEST_TEMPLATE: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<template>
+ <tileset firstgid="1" source="../tilesets/items.tsx"/>
+ <object name="chest" type="loot" gid="2147483651" width="16" height="16">
+  <properties>
+   <property name="locked" type="bool" value="true"/>
+   <property name="loot" type="class" propertytype="Loot">
+    <properties>
+     <property name="gold" type="int" value="10"/>
+     <property name="bonus" type="class" propertytype="Bonus">
+      <properties>
+       <property name="item" value="sword"/>
+      </properties>
+     </property>
+    </properties>
+   </property>
+  </properties>
+ </object>
+</template>"#;
+
+    #[test]
+    fn splits_gid_flags() {
+        assert_eq!(split_gid_flags(0), (0, false, false, false));
+        assert_eq!(split_gid_flags(7), (7, false, false, false));
+        assert_eq!(split_gid_flags(0x80000007), (7, true, false, false));
+        assert_eq!(split_gid_flags(0x40000007), (7, false, true, false));
+        assert_eq!(split_gid_flags(0x20000007), (7, false, false, true));
+        assert_eq!(split_gid_flags(0x10000007), (7, false, false, false));
+        assert_eq!(split_gid_flags(0xF0000007), (7, true, true, true));
+        assert_eq!(split_gid_flags(0xFFFFFFFF), (0x0FFFFFFF, true, true, true));
+    }
+
+    #[test]
+    fn normalizes_colors() {
+        assert_eq!(normalize_color("#80FF0000"), "#ff000080");
+        assert_eq!(normalize_color("ff0000"), "#ff0000ff");
+        assert_eq!(normalize_color("#abc"), "#abc");
+        assert_eq!(normalize_color("#aé€é"), "#aé€é");
+        assert_eq!(normalize_color("#80zz0000"), "#80zz0000");
+        assert_eq!(normalize_color(""), "");
+    }
+
+    #[test]
+    fn reads_quotes_in_json_strings() {
+        let object = serde_json::json!({
+            "name": "The \"Old\" Sign",
+            "type": "<sign> & post",
+            "x": 3,
+            "visible": false,
+            "text": {"text": "He said \"hi\"", "fontfamily": "Comic \"Sans\"", "color": "#80ff0000"},
+        });
+        let parsed = parse_json_object(&object);
+        assert_eq!(parsed.name, "The \"Old\" Sign");
+        assert_eq!(parsed.obj_type, "<sign> & post");
+        assert_eq!((parsed.x, parsed.visible), (3.0, false));
+
+        let text = parse_json_text(&object["text"]);
+        assert_eq!(text.font_family, "Comic \"Sans\"");
+        assert_eq!(text.color, "#ff000080");
+
+        let layer = serde_json::json!({"name": "\"Top\" layer"});
+        let parsed = parse_json_layer(&layer, "tile");
+        assert_eq!(parsed.name, "\"Top\" layer");
+
+        let tileset = serde_json::json!({"name": "Tom & \"Jerry\""});
+        let parsed = parse_json_tileset(&tileset);
+        assert_eq!(parsed.name, "Tom & \"Jerry\"");
+    }
+
+    #[test]
+    fn reads_json_numbers() {
+        let layer = serde_json::json!({"offsetx": 1.5, "offsety": -2.4, "opacity": 0.25});
+        let parsed = parse_json_layer(&layer, "object");
+        assert_eq!((parsed.offset_x, parsed.offset_y), (2, -2));
+        assert_eq!(parsed.opacity, 0.25);
+
+        let object = serde_json::json!({"x": 12.25, "y": 7, "gid": 2147483651u32});
+        let parsed = parse_json_object(&object);
+        assert_eq!((parsed.x, parsed.y), (12.25, 7.0));
+        assert_eq!((parsed.gid, parsed.flip_h), (Some(3), true));
+
+        let tileset = serde_json::json!({
+            "tilewidth": 16,
+            "tileheight": 16,
+            "tileoffset": {"x": 0.6, "y": -4},
+            "grid": {"orientation": "isometric", "width": 32},
+            "transformations": {"hflip": true},
+        });
+        let parsed = parse_json_tileset(&tileset);
+        assert_eq!((parsed.tile_offset_x, parsed.tile_offset_y), (1, -4));
+        assert_eq!((parsed.grid_width, parsed.grid_height), (32, 16));
+        assert_eq!(parsed.grid_orientation, "isometric");
+        assert!(parsed.allow_flip_h && !parsed.allow_flip_v);
+
+        let map = serde_json::json!({"width": 10, "height": 8, "version": 1.2, "infinite": true});
+        let parsed = parse_json_map(&map);
+        assert_eq!((parsed.width, parsed.height), (10, 8));
+        assert_eq!(parsed.version, "1.2");
+        assert!(parsed.infinite);
+    }
+
+    #[test]
+    fn unescapes_tmx_attributes() {
+        let e = start(r#"<object name="Tom &amp; &quot;Jerry&quot;" type="a&lt;b"/>"#);
+        let parsed = parse_object(&e).unwrap();
+        assert_eq!(
+            (parsed.name.as_str(), parsed.obj_type.as_str()),
+            ("Tom & \"Jerry\"", "a<b")
+        );
+    }
+
+    #[test]
+    fn unescapes_property_types() {
+        let e = start(
+            r#"<property name="loot" type="class" propertytype="Loot &amp; &quot;Drops&quot;"/>"#,
+        );
+        let parsed = parse_property(&e).unwrap();
+        assert_eq!(parsed.value_type, "class");
+        assert_eq!(parsed.property_type, "Loot & \"Drops\"");
+    }
+
+    #[test]
+    fn unescapes_image_sources() {
+        let e =
+            start(r#"<image source="../art/tom &amp; jerry&apos;s.png" width="64" height="32"/>"#);
+        assert_eq!(
+            parse_image(&e).unwrap(),
+            (
+                Some("../art/tom & jerry's.png".to_string()),
+                Some(64),
+                Some(32)
+            )
+        );
+    }
+
+    #[test]
+    fn resolves_paths() {
+        assert_eq!(resolve_path("", "terrain.tsx"), "terrain.tsx");
+        assert_eq!(resolve_path("maps", "./terrain.tsx"), "maps/terrain.tsx");
+        assert_eq!(
+            resolve_path("maps", "../tilesets/./t.tsx"),
+            "tilesets/t.tsx"
+        );
+        assert_eq!(resolve_path("maps", "tilesets/../t.tsx"), "maps/t.tsx");
+        assert_eq!(
+            resolve_path("maps", "../../shared/t.tsx"),
+            "../shared/t.tsx"
+        );
+        assert_eq!(resolve_path("", "../../t.tsx"), "../../t.tsx");
+        assert_eq!(
+            resolve_path("./maps/", "tilesets\\t.tsx"),
+            "maps/tilesets/t.tsx"
+        );
+        assert_eq!(resolve_path("/srv/maps", "../t.tsx"), "/srv/t.tsx");
+        assert_eq!(resolve_path("maps", "/srv/t.tsx"), "/srv/t.tsx");
+        assert_eq!(resolve_path("/", "../t.tsx"), "/t.tsx");
+        assert_eq!(parent_dir("maps/town.tmx"), "maps");
+        assert_eq!(parent_dir("/town.tmx"), "/");
+        assert_eq!(parent_dir("town.tmx"), "");
+    }
+
+    #[test]
+    fn decodes_csv() {
+        let text = "0,1,\n2147483650,42\n";
+        assert_eq!(decode_tile_data(text, Some("csv"), None).unwrap(), GIDS);
+    }
+
+    #[test]
+    fn decodes_base64() {
+        let text = format!("\n   {}\n  ", base64(&tile_bytes()));
+        assert_eq!(decode_tile_data(&text, Some("base64"), None).unwrap(), GIDS);
+        assert_eq!(
+            decode_tile_data(&text, Some("base64"), Some("")).unwrap(),
+            GIDS
+        );
+    }
+
+    #[test]
+    fn decodes_zlib() {
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), Default::default());
+        encoder.write_all(&tile_bytes()).unwrap();
+        let text = base64(&encoder.finish().unwrap());
+        assert_eq!(
+            decode_tile_data(&text, Some("base64"), Some("zlib")).unwrap(),
+            GIDS
+        );
+    }
+
+    #[test]
+    fn decodes_gzip() {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), Default::default());
+        encoder.write_all(&tile_bytes()).unwrap();
+        let text = base64(&encoder.finish().unwrap());
+        assert_eq!(
+            decode_tile_data(&text, Some("base64"), Some("gzip")).unwrap(),
+            GIDS
+        );
+    }
+
+    #[test]
+    fn decodes_zstd() {
+        let compressed = ruzstd::encoding::compress_to_vec(
+            tile_bytes().as_slice(),
+            ruzstd::encoding::CompressionLevel::Fastest,
+        );
+        let text = base64(&compressed);
+        assert_eq!(
+            decode_tile_data(&text, Some("base64"), Some("zstd")).unwrap(),
+            GIDS
+        );
+    }
+
+    #[test]
+    fn reads_xml_tile_data() {
+        let data = r#"<data>
+            <tile/><tile gid="1"/><tile gid="2147483650"/><tile gid="42"></tile>
+        </data>"#;
+        let mut reader = xml_reader(data);
+        let mut gids = Vec::new();
+        loop {
+            match reader.read_event().unwrap() {
+                quick_xml::events::Event::Start(e) if e.name().as_ref() == b"tile" => {
+                    gids.push(parse_data_tile(&e).unwrap());
+                }
+                quick_xml::events::Event::Eof => break,
+                _ => {}
+            }
+        }
+        assert_eq!(gids, GIDS);
+    }
+
+    #[test]
+    fn rejects_bad_tile_data() {
+        let text = base64(&tile_bytes());
+        assert_eq!(
+            decode_tile_data(&text, Some("base64"), Some("lzma")).unwrap_err(),
+            "Unsupported tile data compression 'lzma'"
+        );
+        assert!(decompress_tile_data(tile_bytes(), Some("zlib")).is_err());
+        assert!(decode_tile_data(&base64(&[1, 2, 3]), Some("base64"), None).is_err());
+        assert!(decode_tile_data("1,2", Some("json"), None).is_err());
+        assert!(decode_tile_data("", None, None).is_err());
+    }
+
+    #[test]
+    fn places_chunk_tiles() {
+        let tmx = r#"<map width="3" height="2" tilewidth="16" tileheight="16" infinite="1">
+ <layer id="1" name="chunks">
+  <data encoding="csv">
+   <chunk x="-16" y="0" width="2" height="2">1,0,
+0,2147483650</chunk>
+   <chunk x="16" y="-16" width="2" height="1">0,3</chunk>
+  </data>
+ </layer>
+ <layer id="2" name="xml chunk">
+  <data>
+   <chunk x="-32" y="16" width="2" height="1"><tile/><tile gid="4"/></chunk>
+  </data>
+ </layer>
+ <layer id="3" name="finite">
+  <data encoding="csv">0,0,0,
+5,0,0</data>
+ </layer>
+ <layer id="4" name="empty"><data encoding="csv">0,0,0,0,0,0</data></layer>
+</map>"#;
+        let rows = parse_tmx_rows(
+            "test.tmx",
+            tmx,
+            &HashMap::new(),
+            FirstIds {
+                tile: 100,
+                layer: 7,
+                ..FirstIds::default()
+            },
+            |_, _| None,
+        )
+        .unwrap();
+        assert!(rows.map.infinite);
+
+        let tiles: Vec<_> = rows
+            .tiles
+            .iter()
+            .map(|tile| {
+                (
+                    tile.tile_id,
+                    tile.layer_id,
+                    tile.x,
+                    tile.y,
+                    tile.gid,
+                    tile.flip_h,
+                )
+            })
+            .collect();
+        assert_eq!(
+            tiles,
+            [
+                (100, 7, -16, 0, 1, false),
+                (101, 7, -15, 1, 2, true),
+                (102, 7, 17, -16, 3, false),
+                (103, 8, -31, 16, 4, false),
+                (104, 9, 0, 1, 5, false),
+            ]
+        );
+
+        let bounds: Vec<_> = rows
+            .layers
+            .iter()
+            .map(|layer| (layer.min_x, layer.min_y, layer.max_x, layer.max_y))
+            .collect();
+        assert_eq!(
+            bounds,
+            [
+                (Some(-16), Some(-16), Some(17), Some(1)),
+                (Some(-31), Some(16), Some(-31), Some(16)),
+                (Some(0), Some(1), Some(0), Some(1)),
+                (None, None, None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn links_properties_to_their_owner() {
+        let tmx = r##"<map width="1" height="1" tilewidth="16" tileheight="16">
+ <properties>
+  <property name="music" value="town.ogg"/>
+ </properties>
+ <tileset firstgid="1" name="terrain" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+  <properties><property name="biome" value="grass"/></properties>
+  <tile id="3">
+   <properties><property name="solid" type="bool" value="true"/></properties>
+  </tile>
+ </tileset>
+ <layer id="1" name="ground" width="1" height="1">
+  <properties><property name="depth" type="int" value="2"/></properties>
+  <data encoding="csv">1</data>
+ </layer>
+ <objectgroup id="2" name="things">
+  <properties><property name="spawns" type="bool" value="false"/></properties>
+  <object id="1" name="sign">
+   <properties>
+    <property name="message">Hello
+  world</property>
+    <property name="loot" type="class" propertytype="Loot">
+     <properties>
+      <property name="gold" type="int" value="3"/>
+      <property name="bonus" type="class" propertytype="Bonus">
+       <properties><property name="item" value="key"/></properties>
+      </property>
+     </properties>
+    </property>
+    <property name="tint" type="color" value="#80ff0000"/>
+   </properties>
+  </object>
+ </objectgroup>
+</map>"##;
+        let rows = parse_rows(tmx, &[]);
+        let tile = tileset_tile_key(0, 3);
+
+        assert_eq!(
+            property_links(&rows),
+            [
+                ("map", 0, "music", "town.ogg"),
+                ("tileset", 0, "biome", "grass"),
+                ("tileset_tile", tile, "solid", "true"),
+                ("layer", 0, "depth", "2"),
+                ("layer", 1, "spawns", "false"),
+                ("object", 0, "message", "Hello\n  world"),
+                ("object", 0, "loot", ""),
+                ("property", 6, "gold", "3"),
+                ("property", 6, "bonus", ""),
+                ("property", 8, "item", "key"),
+                ("object", 0, "tint", "#ff000080"),
+            ]
+        );
+        assert_eq!(rows.properties[6].property_type, "Loot");
+        assert_eq!(rows.properties[10].value_type, "color");
+    }
+
+    #[test]
+    fn links_group_layers() {
+        let tmx = r#"<map width="1" height="1" tilewidth="16" tileheight="16">
+ <layer id="1" name="ground" width="1" height="1"><data encoding="csv">0</data></layer>
+ <group id="2" name="town" offsetx="8">
+  <objectgroup id="3" name="npcs"/>
+  <group id="4" name="roofs">
+   <imagelayer id="5" name="sky"><image source="sky.png" width="64" height="64"/></imagelayer>
+  </group>
+  <layer id="6" name="walls" width="1" height="1"><data encoding="csv">0</data></layer>
+ </group>
+ <layer id="7" name="top" width="1" height="1"><data encoding="csv">0</data></layer>
+</map>"#;
+        let rows = parse_tmx_rows(
+            "test.tmx",
+            tmx,
+            &HashMap::new(),
+            FirstIds {
+                layer: 10,
+                ..FirstIds::default()
+            },
+            |_, _| None,
+        )
+        .unwrap();
+
+        let layers: Vec<_> = rows
+            .layers
+            .iter()
+            .map(|layer| {
+                (
+                    layer.layer_id,
+                    layer.name.as_str(),
+                    layer.layer_type.as_str(),
+                    layer.parent_layer_id,
+                    layer.z_order,
+                )
+            })
+            .collect();
+        assert_eq!(
+            layers,
+            [
+                (10, "ground", "tile", None, 0),
+                (11, "town", "group", None, 1),
+                (12, "npcs", "object", Some(11), 2),
+                (13, "roofs", "group", Some(11), 3),
+                (14, "sky", "image", Some(13), 4),
+                (15, "walls", "tile", Some(11), 5),
+                (16, "top", "tile", None, 6),
+            ]
+        );
+        assert_eq!(rows.layers[1].offset_x, 8);
+        assert_eq!(rows.image_layers[0].layer_id, 14);
+        assert_eq!(
+            rows.image_layers[0].image_source.as_deref(),
+            Some("sky.png")
+        );
+    }
+
+    #[test]
+    fn stores_tile_collision_shapes() {
+        let tsx = r#"<tileset name="props" tilewidth="16" tileheight="16" tilecount="8" columns="4">
+ <tile id="5">
+  <objectgroup draworder="index" id="2">
+   <properties><property name="ignored" value="group"/></properties>
+   <object id="1" name="base" x="2" y="4" width="12" height="10">
+    <properties><property name="material" value="wood"/></properties>
+   </object>
+   <object id="2" x="1" y="1">
+    <polygon points="0,0 4,0 4,4"/>
+   </object>
+  </objectgroup>
+ </tile>
+</tileset>"#;
+        let tmx = r#"<map width="1" height="1" tilewidth="16" tileheight="16">
+ <tileset firstgid="1" source="../tilesets/props.tsx"/>
+ <objectgroup id="1" name="things">
+  <properties><property name="layer" value="things"/></properties>
+  <object id="3" name="crate" x="8" y="8" width="16" height="16"/>
+ </objectgroup>
+</map>"#;
+        let rows = parse_rows(tmx, &[("tilesets/props.tsx", tsx)]);
+        let tile = tileset_tile_key(0, 5);
+
+        // The collision group is not a layer, and its objects aren't map objects
+        assert_eq!(rows.layers.len(), 1);
+        assert_eq!(rows.objects.len(), 1);
+        assert_eq!(rows.objects[0].layer_id, rows.layers[0].layer_id);
+
+        let collisions: Vec<_> = rows
+            .collisions
+            .iter()
+            .map(|c| {
+                (
+                    c.collision_id,
+                    c.tileset_tile_id,
+                    c.name.as_str(),
+                    c.shape.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            collisions,
+            [(0, tile, "base", "rectangle"), (1, tile, "", "polygon")]
+        );
+        assert_eq!(rows.collisions[1].points.len(), 3);
+
+        // Collision objects own their properties; the group's own are dropped
+        assert_eq!(
+            property_links(&rows),
+            [
+                ("tile_collision", 0, "material", "wood"),
+                ("layer", 0, "layer", "things"),
+            ]
+        );
+    }
+
+    #[test]
+    fn shares_external_tilesets_with_the_same_content() {
+        let tsx = r#"<tileset name="props" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+ <tile id="1" type="crate"/>
+</tileset>"#;
+        let tmx = r#"<map width="1" height="1" tilewidth="16" tileheight="16">
+ <tileset firstgid="1" source="../tilesets/props.tsx"/>
+ <tileset firstgid="5" source="../tilesets/props.tsx"/>
+</map>"#;
+        let stored = parse_rows(tmx, &[("tilesets/props.tsx", tsx)]);
+        // The second reference shares the first within the same map
+        let definitions: Vec<_> = stored
+            .tilesets
+            .iter()
+            .map(|t| t.definition_tileset_id)
+            .collect();
+        assert_eq!(definitions, [0, 0]);
+
+        let load = |tsx: &str| {
+            let sources = HashMap::from([("tilesets/props.tsx".to_string(), tsx.to_string())]);
+            let ids = FirstIds {
+                tileset: 2,
+                ..FirstIds::default()
+            };
+            parse_tmx_rows("maps/other.tmx", tmx, &sources, ids, |source, hash| {
+                stored
+                    .tilesets
+                    .iter()
+                    .find(|t| is_external_definition(t, source, hash))
+                    .cloned()
+            })
+            .unwrap()
+        };
+
+        let same = load(tsx);
+        assert_eq!(same.tilesets[0].definition_tileset_id, 0);
+        assert!(same.tileset_tiles.is_empty());
+
+        let edited = load(&tsx.replace("crate", "barrel"));
+        assert_eq!(edited.tilesets[0].definition_tileset_id, 2);
+        assert_eq!(edited.tileset_tiles[0].tile_type, "barrel");
+    }
+
+    #[test]
+    fn parses_wang_ids() {
+        let tmx = r##"<map width="1" height="1" tilewidth="16" tileheight="16">
+ <tileset firstgid="1" name="paths" tilewidth="16" tileheight="16" tilecount="4" columns="2">
+  <image source="paths.png" width="32" height="32"/>
+  <wangsets>
+   <wangset name="old" tile="-1">
+    <wangcornercolor name="grass" color="#00ff00" tile="-1" probability="1"/>
+    <wangedgecolor name="road" color="#808080" tile="-1" probability="1"/>
+    <wangtile tileid="0" wangid="0x10101010"/>
+    <wangtile tileid="1" wangid="0x01010101"/>
+   </wangset>
+   <wangset name="new" type="corner" tile="-1">
+    <wangcolor name="sand" color="#ffff00" tile="-1" probability="1"/>
+    <wangtile tileid="2" wangid="0,1,0,1,0,0,0,1"/>
+   </wangset>
+  </wangsets>
+ </tileset>
+</map>"##;
+        let rows = parse_rows(tmx, &[]);
+        let colors: Vec<_> = rows
+            .wang_colors
+            .iter()
+            .map(|c| (c.wang_set_id, c.color_index, c.name.as_str()))
+            .collect();
+        assert_eq!(colors, [(0, 1, "grass"), (0, 2, "road"), (1, 1, "sand")]);
+        let wang_ids: Vec<_> = rows
+            .wang_tiles
+            .iter()
+            .map(|t| t.wang_id.as_slice())
+            .collect();
+        assert_eq!(
+            wang_ids,
+            [
+                [0, 1, 0, 1, 0, 1, 0, 1],
+                [2, 0, 2, 0, 2, 0, 2, 0],
+                [0, 1, 0, 1, 0, 0, 0, 1],
+            ]
+        );
+
+        let legacy = (vec![2], vec![1]);
+        assert!(parse_wang_id("0x20000000", &legacy).is_err());
+        assert!(parse_wang_id("0,1,0,1", &legacy).is_err());
+        assert!(parse_wang_id("0,1,0,x,0,1,0,1", &legacy).is_err());
+    }
+
+    #[test]
+    fn skips_empty_tileset_tiles() {
+        let tmx = r#"<map width="1" height="1" tilewidth="16" tileheight="16">
+ <tileset firstgid="1" name="ground" tilewidth="16" tileheight="16" tilecount="8" columns="4">
+  <image source="ground.png" width="64" height="32"/>
+  <tile id="0"/>
+  <tile id="1" probability="0.5"/>
+  <tile id="2">
+   <properties><property name="solid" type="bool" value="true"/></properties>
+  </tile>
+  <tile id="3">
+   <animation><frame tileid="3" duration="100"/><frame tileid="4" duration="100"/></animation>
+  </tile>
+  <tile id="4"><properties/></tile>
+ </tileset>
+</map>"#;
+        let rows = parse_rows(tmx, &[]);
+        let local_ids: Vec<_> = rows.tileset_tiles.iter().map(|t| t.local_id).collect();
+        assert_eq!(local_ids, [1, 2, 3]);
+    }
+
+    #[test]
+    fn parses_templates() {
+        let template = parse_template("templates/chest.tx", CHEST_TEMPLATE, |source| {
+            (source == "tilesets/items.tsx").then_some(11)
+        })
+        .unwrap();
+        let object =
+            parse_object(&merge_template_attributes(&start("<object/>"), &template).unwrap())
+                .unwrap();
+        assert_eq!(
+            (object.name.as_str(), object.obj_type.as_str()),
+            ("chest", "loot")
+        );
+        // GID 3 of the template's tileset is GID 13 in the map, still flipped
+        assert_eq!((object.gid, object.flip_h), (Some(13), true));
+
+        let properties: Vec<_> = template
+            .properties
+            .iter()
+            .map(|p| {
+                (
+                    p.parent_type.as_str(),
+                    p.parent_id,
+                    p.key.as_str(),
+                    p.value.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            properties,
+            [
+                ("", 0, "locked", "true"),
+                ("", 0, "loot", ""),
+                ("property", 1, "gold", "10"),
+                ("property", 1, "bonus", ""),
+                ("property", 3, "item", "sword"),
+            ]
+        );
+
+        // A tileset the map doesn't have leaves the template without a GID
+        let template = parse_template("templates/chest.tx", CHEST_TEMPLATE, |_| None).unwrap();
+        assert!(!template.attributes.iter().any(|(key, _)| key == b"gid"));
+
+        // The tileset source is unescaped before it's resolved
+        let content = CHEST_TEMPLATE.replace("items.tsx", "tom &amp; jerry.tsx");
+        let template = parse_template("templates/chest.tx", &content, |source| {
+            (source == "tilesets/tom & jerry.tsx").then_some(11)
+        })
+        .unwrap();
+        assert!(template
+            .attributes
+            .iter()
+            .any(|(key, value)| key == b"gid" && value == b"2147483661"));
+    }
+
+    #[test]
+    fn applies_templates_to_objects() {
+        let items =
+            r#"<tileset name="items" tilewidth="16" tileheight="16" tilecount="4" columns="2"/>"#;
+        let sign = r#"<template>
+ <object name="sign" type="info" width="32" height="16">
+  <text wrap="1">Welcome</text>
+ </object>
+</template>"#;
+        let tmx = r#"<map width="1" height="1" tilewidth="16" tileheight="16">
+ <tileset firstgid="11" source="../tilesets/items.tsx"/>
+ <objectgroup id="1" name="things">
+  <object id="1" template="../templates/chest.tx" name="big chest" x="32" y="48">
+   <properties>
+    <property name="locked" type="bool" value="false"/>
+    <property name="loot" type="class" propertytype="Loot">
+     <properties><property name="gold" type="int" value="99"/></properties>
+    </property>
+   </properties>
+  </object>
+  <object id="2" template="../templates/sign.tx" x="0" y="0"/>
+  <object id="3" template="../templates/sign.tx" type="warning" x="0" y="0">
+   <text>Keep out</text>
+  </object>
+  <object id="4" template="../templates/missing.tx" name="orphan"/>
+ </objectgroup>
+</map>"#;
+        let rows = parse_rows(
+            tmx,
+            &[
+                ("tilesets/items.tsx", items),
+                ("templates/chest.tx", CHEST_TEMPLATE),
+                ("templates/sign.tx", sign),
+            ],
+        );
+
+        // Instance attributes win; the rest, the GID included, comes from the template
+        let objects: Vec<_> = rows
+            .objects
+            .iter()
+            .map(|o| (o.name.as_str(), o.obj_type.as_str(), o.x, o.gid, o.flip_h))
+            .collect();
+        assert_eq!(
+            objects,
+            [
+                ("big chest", "loot", 32.0, Some(13), true),
+                ("sign", "info", 0.0, None, false),
+                ("sign", "warning", 0.0, None, false),
+                ("orphan", "", 0.0, None, false),
+            ]
+        );
+        assert_eq!(rows.objects[1].shape, "text");
+        assert_eq!(rows.objects[1].width, 32.0);
+
+        // Instance properties first, then the template members the instance doesn't set,
+        // inside the instance's class
+        assert_eq!(
+            property_links(&rows),
+            [
+                ("object", 0, "locked", "false"),
+                ("object", 0, "loot", ""),
+                ("property", 1, "gold", "99"),
+                ("property", 1, "bonus", ""),
+                ("property", 3, "item", "sword"),
+            ]
+        );
+
+        // An instance's own text replaces the template's
+        let texts: Vec<_> = rows
+            .object_texts
+            .iter()
+            .map(|text| (text.object_id, text.text.as_str(), text.wrap))
+            .collect();
+        assert_eq!(texts, [(1, "Welcome", true), (2, "Keep out", false)]);
+    }
+
+    #[test]
+    fn merges_template_attributes() {
+        let template = parse_template("chest.tx", CHEST_TEMPLATE, |_| Some(1)).unwrap();
+        let instance =
+            start(r#"<object id="7" template="chest.tx" name="big chest" x="32" height="24"/>"#);
+        let object =
+            parse_object(&merge_template_attributes(&instance, &template).unwrap()).unwrap();
+        assert_eq!(
+            (object.name.as_str(), object.obj_type.as_str()),
+            ("big chest", "loot")
+        );
+        assert_eq!((object.x, object.width, object.height), (32.0, 16.0, 24.0));
+        assert_eq!(object.gid, Some(3));
+    }
+
+    #[test]
+    fn merges_template_properties() {
+        let template = parse_template("chest.tx", CHEST_TEMPLATE, |_| None).unwrap();
+        let missing = |instance: &[TiledProperty]| {
+            missing_template_properties(&template.properties, instance)
+                .into_iter()
+                .map(|(parent, p)| (parent, p.key.clone()))
+                .collect::<Vec<_>>()
+        };
+        use TemplatePropertyParent::*;
+
+        // Nothing overridden: everything is stored, members under their stored class
+        assert_eq!(
+            missing(&[]),
+            [
+                (Object, "locked".to_string()),
+                (Object, "loot".to_string()),
+                (Template(1), "gold".to_string()),
+                (Template(1), "bonus".to_string()),
+                (Template(3), "item".to_string()),
+            ]
+        );
+
+        // An overridden plain property is replaced; a member the instance sets is too, and the
+        // class's other members, nested ones included, go into the instance's class
+        let instance = [
+            property(5, ("object", 7), "locked", "false", "bool"),
+            property(6, ("object", 7), "loot", "", "class"),
+            property(8, ("property", 6), "gold", "99", "int"),
+        ];
+        assert_eq!(
+            missing(&instance),
+            [
+                (Instance(6), "bonus".to_string()),
+                (Template(0), "item".to_string())
+            ]
+        );
+
+        // Members of a nested class the instance sets are merged too
+        let instance = [
+            property(6, ("object", 7), "loot", "", "class"),
+            property(8, ("property", 6), "bonus", "", "class"),
+            property(9, ("property", 8), "item", "axe", "string"),
+        ];
+        assert_eq!(
+            missing(&instance),
+            [
+                (Object, "locked".to_string()),
+                (Instance(6), "gold".to_string())
+            ]
+        );
+
+        // A class replaced by a plain value drops its members
+        let instance = [property(6, ("object", 7), "loot", "none", "string")];
+        assert_eq!(missing(&instance), [(Object, "locked".to_string())]);
+    }
+
+    #[test]
+    fn keeps_whitespace_in_text_content() {
+        let content = "<template>
+ <object name=\"sign\">
+  <properties>
+   <property name=\"lore\">  First line
+    indented line
+</property>
+   <property name=\"title\" value=\" padded \"/>
+  </properties>
+  <text wrap=\"1\">  Welcome,
+    traveller  </text>
+ </object>
+</template>";
+        let template = parse_template("sign.tx", content, |_| None).unwrap();
+        let values: Vec<_> = template
+            .properties
+            .iter()
+            .map(|p| p.value.as_str())
+            .collect();
+        assert_eq!(values, ["  First line\n    indented line\n", " padded "]);
+        assert_eq!(template.text.unwrap().text, "  Welcome,\n    traveller  ");
+    }
+
+    fn world_map(map_id: u32, x: i32, y: i32, width: u32, height: u32) -> TiledWorldMap {
+        TiledWorldMap {
+            map_id,
+            world_id: 0,
+            file_name: String::new(),
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn matches_world_patterns() {
+        let pattern = |value| parse_world_pattern(&value).unwrap();
+
+        let plain = pattern(serde_json::json!({
+            "regexp": "map_(-?\\d+)_(-?\\d+)\\.tmx",
+            "multiplierX": 320,
+            "multiplierY": 240,
+        }));
+        assert_eq!(
+            world_pattern_origin(&plain, "map_2_-1.tmx"),
+            Some((640, -240))
+        );
+        assert_eq!(plain.map_size, (Some(320), Some(240)));
+
+        let shifted = pattern(serde_json::json!({
+            "regexp": "map_(\\d+)_(\\d+)\\.tmx",
+            "multiplierX": 320,
+            "multiplierY": 240,
+            "offsetX": -160,
+            "offsetY": 16,
+            "mapWidth": 400,
+        }));
+        assert_eq!(
+            world_pattern_origin(&shifted, "map_1_0.tmx"),
+            Some((160, 16))
+        );
+        assert_eq!(shifted.map_size, (Some(400), Some(240)));
+
+        // Files the pattern doesn't match, or whose captures aren't numbers
+        assert_eq!(world_pattern_origin(&shifted, "town.tmx"), None);
+        assert_eq!(world_pattern_origin(&shifted, "map_-1_0.tmx"), None);
+        let optional = pattern(serde_json::json!({"regexp": "m(\\d+)?_(\\w+)\\.tmx"}));
+        assert_eq!(world_pattern_origin(&optional, "m_1.tmx"), None);
+        assert_eq!(world_pattern_origin(&optional, "m1_x.tmx"), None);
+
+        // Positions past the range of an i32
+        assert_eq!(world_pattern_origin(&plain, "map_99999999_0.tmx"), None);
+        assert_eq!(world_pattern_origin(&shifted, "map_0_9999999.tmx"), None);
+        let edge = pattern(serde_json::json!({
+            "regexp": "map_(\\d+)_(\\d+)\\.tmx",
+            "offsetX": i32::MAX,
+        }));
+        assert_eq!(
+            world_pattern_origin(&edge, "map_0_0.tmx"),
+            Some((i32::MAX, 0))
+        );
+        assert_eq!(world_pattern_origin(&edge, "map_1_0.tmx"), None);
+
+        assert!(parse_world_pattern(&serde_json::json!({"regexp": "map_(\\d+)\\.tmx"})).is_err());
+        assert!(parse_world_pattern(&serde_json::json!({"regexp": "map_("})).is_err());
+    }
+
+    #[test]
+    fn lists_world_maps() {
+        let world = serde_json::json!({
+            "maps": [
+                {"fileName": "./town.tmx", "x": -100, "y": 50, "width": 320, "height": 240},
+                {"fileName": "map_0_0.tmx", "x": 7, "y": 7},
+            ],
+            "patterns": [
+                {"regexp": "map_(\\d+)_(\\d+)\\.tmx", "multiplierX": 320, "multiplierY": 240},
+            ],
+        });
+        let file_names = ["map_0_0.tmx", "map_1_2.tmx", "notes.txt", "town.tmx"].map(String::from);
+
+        assert_eq!(
+            world_map_entries(&world, &file_names).unwrap(),
+            [
+                ("town.tmx".to_string(), -100, 50, Some(320), Some(240)),
+                // A map listed explicitly isn't placed again by a pattern
+                ("map_0_0.tmx".to_string(), 7, 7, None, None),
+                ("map_1_2.tmx".to_string(), 320, 480, Some(320), Some(240)),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_world_maps() {
+        let map = world_map(0, -320, 0, 320, 240);
+
+        assert!(world_map_covers(&map, -320.0, 0.0));
+        assert!(world_map_covers(&map, -0.5, 239.5));
+        // The right and bottom edges belong to the next map
+        assert!(!world_map_covers(&map, 0.0, 10.0));
+        assert!(!world_map_covers(&map, -10.0, 240.0));
+        assert!(!world_map_covers(&map, -321.0, 10.0));
+
+        // Edges and corners touch, a gap doesn't
+        assert!(world_maps_touch(&map, &world_map(1, 0, 0, 320, 240)));
+        assert!(world_maps_touch(&map, &world_map(2, -320, 240, 320, 240)));
+        assert!(world_maps_touch(&map, &world_map(3, 0, 240, 320, 240)));
+        assert!(world_maps_touch(&map, &world_map(4, -640, -240, 320, 240)));
+        assert!(world_maps_touch(&map, &world_map(5, -100, 100, 10, 10)));
+        assert!(!world_maps_touch(&map, &world_map(6, 1, 0, 320, 240)));
+        assert!(!world_maps_touch(&map, &world_map(7, 0, 241, 320, 240)));
+    }
+
+    #[test]
+    fn sizes_world_maps() {
+        let map = |value| parse_json_map(&value);
+        let fixed =
+            map(serde_json::json!({"width": 20, "height": 15, "tilewidth": 16, "tileheight": 16}));
+        assert_eq!(world_map_size(&fixed, &[]).unwrap(), (320, 240));
+
+        let huge = map(
+            serde_json::json!({"width": 9999999, "height": 1, "tilewidth": 1000, "tileheight": 1}),
+        );
+        assert!(world_map_size(&huge, &[]).is_err());
+
+        // Infinite maps cover their tiles, wherever they are
+        let infinite =
+            map(serde_json::json!({"infinite": true, "tilewidth": 16, "tileheight": 16}));
+        let bounded = |min_x, min_y, max_x, max_y| TiledLayer {
+            min_x: Some(min_x),
+            min_y: Some(min_y),
+            max_x: Some(max_x),
+            max_y: Some(max_y),
+            ..parse_json_layer(&serde_json::json!({}), "tile")
+        };
+        let layers = [
+            bounded(-16, 0, 15, 15),
+            bounded(0, -4, 31, 3),
+            parse_json_layer(&serde_json::json!({}), "object"),
+        ];
+        assert_eq!(world_map_size(&infinite, &layers).unwrap(), (768, 320));
+        assert_eq!(world_map_size(&infinite, &layers[2..]).unwrap(), (0, 0));
+        assert!(world_map_size(&infinite, &[bounded(i32::MIN, 0, i32::MAX, 0)]).is_err());
+    }
+
+    #[test]
+    fn decodes_json_tile_data() {
+        let plain = serde_json::json!({"data": GIDS});
+        assert_eq!(decode_json_tile_data(&plain, &plain).unwrap(), GIDS);
+
+        // Strings are base64 unless the layer says otherwise; chunks use the layer's encoding
+        let mut encoder = flate2::write::ZlibEncoder::new(Vec::new(), Default::default());
+        encoder.write_all(&tile_bytes()).unwrap();
+        let layer = serde_json::json!({"encoding": "base64", "compression": "zlib"});
+        let chunk = serde_json::json!({"data": base64(&encoder.finish().unwrap())});
+        assert_eq!(decode_json_tile_data(&chunk, &layer).unwrap(), GIDS);
+
+        let raw = serde_json::json!({"data": base64(&tile_bytes())});
+        assert_eq!(decode_json_tile_data(&raw, &raw).unwrap(), GIDS);
+
+        let layer = serde_json::json!({"compression": "zlib"});
+        assert!(decode_json_tile_data(&raw, &layer).is_err());
+        assert!(decode_json_tile_data(&serde_json::json!({}), &layer)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn merges_json_templates() {
+        let template = serde_json::json!({
+            "name": "chest",
+            "type": "loot",
+            "width": 16,
+            "properties": [
+                {"name": "locked", "type": "bool", "value": true},
+                {"name": "loot", "type": "class", "propertytype": "Loot", "value": {
+                    "gold": 10,
+                    "bonus": {"item": "sword", "count": 1},
+                }},
+            ],
+        });
+        let object = serde_json::json!({
+            "id": 7,
+            "name": "big chest",
+            "x": 32,
+            "properties": [
+                {"name": "locked", "type": "bool", "value": false},
+                {"name": "loot", "type": "class", "propertytype": "Loot", "value": {
+                    "bonus": {"item": "axe"},
+                }},
+                {"name": "note", "type": "string", "value": "new"},
+            ],
+        });
+
+        let merged = merge_json_template(&template, &object);
+        assert_eq!(
+            (json_str(&merged, "name"), json_str(&merged, "type")),
+            (Some("big chest"), Some("loot"))
+        );
+        assert_eq!(
+            (json_i32(&merged, "x"), json_i32(&merged, "width")),
+            (Some(32), Some(16))
+        );
+        assert_eq!(
+            merged["properties"],
+            serde_json::json!([
+                {"name": "locked", "type": "bool", "value": false},
+                {"name": "loot", "type": "class", "propertytype": "Loot", "value": {
+                    "gold": 10,
+                    "bonus": {"item": "axe", "count": 1},
+                }},
+                {"name": "note", "type": "string", "value": "new"},
+            ])
+        );
+
+        // A class replaced by a plain value drops its members
+        let object = serde_json::json!({"properties": [{"name": "loot", "value": "none"}]});
+        let merged = merge_json_template(&template, &object);
+        assert_eq!(
+            merged["properties"][1],
+            serde_json::json!({"name": "loot", "value": "none"})
+        );
+    }
+
+    #[test]
+    fn types_json_class_members() {
+        let member = serde_json::json!({"type": "color", "propertytype": "", "value": "#80ff0000"});
+        let (value_type, property_type, value) = json_member_type(&member);
+        assert_eq!((value_type, property_type), ("color", ""));
+        assert_eq!(json_property_value(value, value_type), "#ff000080");
+
+        let member =
+            serde_json::json!({"type": "class", "propertytype": "Bonus", "value": {"count": 1}});
+        let (value_type, property_type, value) = json_member_type(&member);
+        assert_eq!(
+            (value_type, property_type, value),
+            ("class", "Bonus", Some(&member["value"]))
+        );
+        assert_eq!(json_property_value(value, value_type), "");
+
+        // Without a declared type, the JSON value decides
+        let inferred = |member: serde_json::Value| {
+            let (value_type, property_type, value) = json_member_type(&member);
+            (
+                value_type.to_string(),
+                property_type.to_string(),
+                json_property_value(value, value_type),
+            )
+        };
+        assert_eq!(
+            inferred(serde_json::json!(3)),
+            ("int".into(), "".into(), "3".into())
+        );
+        assert_eq!(
+            inferred(serde_json::json!(0.5)),
+            ("float".into(), "".into(), "0.5".into())
+        );
+        assert_eq!(
+            inferred(serde_json::json!(true)),
+            ("bool".into(), "".into(), "true".into())
+        );
+        assert_eq!(
+            inferred(serde_json::json!("#80ff0000")),
+            ("string".into(), "".into(), "#80ff0000".into())
+        );
+        // An object named like a property's fields but without a value is a nested class
+        assert_eq!(inferred(serde_json::json!({"type": "sword"})).0, "class");
+    }
+}